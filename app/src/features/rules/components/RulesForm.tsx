@@ -17,9 +17,9 @@ import { BlackjackPayoutSelect } from "./BlackjackPayoutSelect";
 
 export const rulesFormSchema = z.object({
   blackjackPayout: z.enum(["Ratio3to2", "Ratio6to5"]),
-  numDecks: z.coerce.number().min(1).max(255),
+  numDecks: z.coerce.number().min(1).max(15),
   dealerSoft17: z.enum(["Hit", "Stand"]),
-  maxSplits: z.coerce.number().min(1).max(255),
+  maxSplits: z.coerce.number().min(1).max(7),
   doubleAfterSplitAllowed: z.boolean(),
   surrender: z.enum(["None", "Early", "Late"]),
 });
@@ -0,0 +1,77 @@
+use std::{
+    fs::{self, File},
+    hash::Hash,
+    io::{BufReader, BufWriter, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use engine::{shoe::Shoe, strategy::StrategyGenerator};
+
+#[derive(Args)]
+pub struct CacheArgs {
+    /// Directory keeping solved states between runs, one file per rules, shoe and precision
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Memory the solver caches may use, in megabytes
+    #[arg(long)]
+    pub cache_limit: Option<usize>,
+
+    /// Print cache statistics once solved
+    #[arg(long)]
+    pub cache_stats: bool,
+}
+
+impl CacheArgs {
+    fn cache_file<S: Shoe + Clone + Eq + Hash>(
+        &self,
+        generator: &StrategyGenerator<S>,
+    ) -> Option<PathBuf> {
+        let dir = self.cache_dir.as_ref()?;
+        Some(dir.join(format!("{:016x}.cache", generator.cache_fingerprint())))
+    }
+
+    // Saved caches only speed things up, so one that cannot be read is ignored
+    pub fn load<S: Shoe + Clone + Eq + Hash>(&self, generator: &mut StrategyGenerator<S>) {
+        generator.set_cache_limit(self.cache_limit.map(|megabytes| megabytes << 20));
+
+        let Some(path) = self.cache_file(generator) else {
+            return;
+        };
+        let Ok(file) = File::open(&path) else {
+            return;
+        };
+        if let Err(error) = generator.load_caches(&mut BufReader::new(file)) {
+            eprintln!("Ignoring cache {}: {error}", path.display());
+        }
+    }
+
+    pub fn save<S: Shoe + Clone + Eq + Hash>(
+        &self,
+        generator: &StrategyGenerator<S>,
+    ) -> Result<()> {
+        let Some(path) = self.cache_file(generator) else {
+            return Ok(());
+        };
+        let dir = path
+            .parent()
+            .expect("cache files are in the cache directory");
+        fs::create_dir_all(dir).with_context(|| format!("Could not create {}", dir.display()))?;
+
+        // Written aside first so that an interrupted run never leaves a truncated cache
+        let partial = path.with_extension("partial");
+        let mut writer = BufWriter::new(
+            File::create(&partial)
+                .with_context(|| format!("Could not create {}", partial.display()))?,
+        );
+        generator
+            .save_caches(&mut writer)
+            .and_then(|()| writer.flush())
+            .with_context(|| format!("Could not write {}", partial.display()))?;
+        fs::rename(&partial, &path)
+            .with_context(|| format!("Could not write {}", path.display()))?;
+        Ok(())
+    }
+}
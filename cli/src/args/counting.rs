@@ -0,0 +1,29 @@
+use engine::counting::CountingSystem;
+
+pub fn parse_system(name: &str) -> Result<CountingSystem, String> {
+    CountingSystem::find(name).copied().ok_or_else(|| {
+        let names: Vec<_> = CountingSystem::all().map(|system| system.name).collect();
+        format!(
+            "Unknown counting system {name}, expected one of: {}",
+            names.join(", ")
+        )
+    })
+}
+
+// Reads ten comma separated tags, from two to ace
+pub fn parse_tags(value: &str) -> Result<CountingSystem, String> {
+    let tags = value
+        .split(',')
+        .map(|tag| {
+            tag.trim()
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid tag {tag}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let tags: [f64; 10] = tags.try_into().map_err(|tags: Vec<f64>| {
+        format!("Expected 10 tags from two to ace, got {}", tags.len())
+    })?;
+
+    Ok(CountingSystem::custom(tags))
+}
@@ -1 +1,5 @@
+pub mod cache;
+pub mod counting;
+pub mod precision;
 pub mod rules;
+pub mod sweep;
@@ -0,0 +1,135 @@
+use std::fmt::Display;
+
+use clap::{Args, ValueEnum};
+use engine::{
+    error::Error,
+    options::{GeneratorOptions, PruningStrategy, Solver, SplitMode},
+};
+
+#[derive(ValueEnum, Clone)]
+pub enum PruningArg {
+    Branch,
+    Draw,
+    None,
+}
+
+impl Display for PruningArg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PruningArg::Branch => "branch",
+                PruningArg::Draw => "draw",
+                PruningArg::None => "none",
+            }
+        )
+    }
+}
+
+impl From<&PruningArg> for PruningStrategy {
+    fn from(value: &PruningArg) -> Self {
+        match value {
+            PruningArg::Branch => PruningStrategy::Branch,
+            PruningArg::Draw => PruningStrategy::Draw,
+            PruningArg::None => PruningStrategy::None,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone)]
+pub enum SplitModeArg {
+    Independent,
+    Sequential,
+}
+
+impl Display for SplitModeArg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                SplitModeArg::Independent => "independent",
+                SplitModeArg::Sequential => "sequential",
+            }
+        )
+    }
+}
+
+impl From<&SplitModeArg> for SplitMode {
+    fn from(value: &SplitModeArg) -> Self {
+        match value {
+            SplitModeArg::Independent => SplitMode::Independent,
+            SplitModeArg::Sequential => SplitMode::Sequential,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone)]
+pub enum SolverArg {
+    Recursive,
+    Iterative,
+}
+
+impl Display for SolverArg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                SolverArg::Recursive => "recursive",
+                SolverArg::Iterative => "iterative",
+            }
+        )
+    }
+}
+
+impl From<&SolverArg> for Solver {
+    fn from(value: &SolverArg) -> Self {
+        match value {
+            SolverArg::Recursive => Solver::Recursive,
+            SolverArg::Iterative => Solver::Iterative,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct PrecisionArgs {
+    /// Probability below which branches are pruned
+    #[arg(long, default_value_t = GeneratorOptions::default().epsilon)]
+    pub precision: f64,
+
+    /// Probability compared to the precision when pruning
+    #[arg(long, default_value_t = PruningArg::Branch)]
+    pub pruning: PruningArg,
+
+    /// Most cards a player hand may hold, the player stands instead of drawing past it
+    #[arg(long)]
+    pub max_depth: Option<u8>,
+
+    /// Whether the second hand of a split is drawn from the cards the first one left
+    #[arg(long, default_value_t = SplitModeArg::Independent)]
+    pub split_mode: SplitModeArg,
+
+    /// Whether hands are hit through recursive calls or an explicit stack, which gives the
+    /// same results without risking a stack overflow
+    #[arg(long, default_value_t = SolverArg::Recursive)]
+    pub solver: SolverArg,
+}
+
+impl TryFrom<&PrecisionArgs> for GeneratorOptions {
+    type Error = Error;
+
+    fn try_from(value: &PrecisionArgs) -> Result<Self, Self::Error> {
+        let options = GeneratorOptions {
+            epsilon: value.precision,
+            pruning: (&value.pruning).into(),
+            max_depth: value.max_depth,
+            split: (&value.split_mode).into(),
+            solver: (&value.solver).into(),
+        };
+
+        options.validate()?;
+        Ok(options)
+    }
+}
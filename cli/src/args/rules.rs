@@ -1,7 +1,11 @@
-use std::fmt::Display;
+use std::{fmt::Display, fs, path::Path};
 
 use clap::{Args, ValueEnum};
-use engine::rules::{BlackjackPayout, Rules, Soft17Rule, SurrenderType};
+use engine::{
+    error::Error,
+    preset::Preset,
+    rules::{BlackjackPayout, Rules, Soft17Rule, SurrenderType},
+};
 
 #[derive(ValueEnum, Clone)]
 pub enum BlackjackPayoutArg {
@@ -92,36 +96,159 @@ impl From<&Soft17RuleArg> for Soft17Rule {
     }
 }
 
+fn parse_preset(name: &str) -> Result<Preset, String> {
+    name.parse().map_err(|error: Error| error.to_string())
+}
+
+// Reads a complete set of rules from a JSON file, or TOML for any other extension
+fn parse_rules_file(path: &str) -> Result<Rules, String> {
+    let content =
+        fs::read_to_string(path).map_err(|error| format!("Could not read {path}: {error}"))?;
+
+    let is_json = Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    if is_json {
+        serde_json::from_str(&content)
+            .map_err(|error| format!("Invalid rules file {path}: {error}"))
+    } else {
+        toml::from_str(&content).map_err(|error| format!("Invalid rules file {path}: {error}"))
+    }
+}
+
 #[derive(Args)]
 pub struct RulesArgs {
-    #[arg(long, default_value_t = BlackjackPayoutArg::Ratio3to2)]
-    pub blackjack_payout: BlackjackPayoutArg,
+    /// Named rule preset, individual rule flags override its values
+    #[arg(long, value_parser = parse_preset)]
+    pub preset: Option<Preset>,
+
+    /// TOML or JSON rules file, individual rule flags override its values
+    #[arg(long, value_parser = parse_rules_file, conflicts_with = "preset")]
+    pub rules_file: Option<Rules>,
 
-    #[arg(long, default_value_t = 6)]
-    pub num_decks: u8,
+    #[arg(long)]
+    pub blackjack_payout: Option<BlackjackPayoutArg>,
 
-    #[arg(long, default_value_t = Soft17RuleArg::Stand)]
-    pub dealer_soft_17: Soft17RuleArg,
+    #[arg(long)]
+    pub num_decks: Option<u8>,
 
     #[arg(long)]
-    pub double_after_split_allowed: bool,
+    pub dealer_soft_17: Option<Soft17RuleArg>,
 
-    #[arg(long, default_value_t = SurrenderArg::None)]
-    pub surrender: SurrenderArg,
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub double_after_split_allowed: Option<bool>,
 
-    #[arg(long, default_value_t = 6)]
-    pub max_splits: u8,
+    #[arg(long)]
+    pub surrender: Option<SurrenderArg>,
+
+    #[arg(long)]
+    pub max_splits: Option<u8>,
+
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub dealer_peek: Option<bool>,
 }
 
-impl From<&RulesArgs> for Rules {
-    fn from(value: &RulesArgs) -> Self {
-        Self {
-            blackjack_payout: (&value.blackjack_payout).into(),
-            num_decks: value.num_decks,
-            surrender: (&value.surrender).into(),
-            double_after_split_allowed: value.double_after_split_allowed,
-            max_splits: value.max_splits,
-            dealer_soft_17: (&value.dealer_soft_17).into(),
+impl TryFrom<&RulesArgs> for Rules {
+    type Error = Error;
+
+    fn try_from(value: &RulesArgs) -> Result<Self, Self::Error> {
+        let mut rules = match (&value.preset, &value.rules_file) {
+            (Some(preset), _) => preset.rules,
+            (None, Some(rules)) => *rules,
+            (None, None) => Rules {
+                max_splits: 6,
+                ..Rules::default()
+            },
+        };
+
+        if let Some(blackjack_payout) = &value.blackjack_payout {
+            rules.blackjack_payout = blackjack_payout.into();
+        }
+        if let Some(num_decks) = value.num_decks {
+            rules.num_decks = num_decks;
+        }
+        if let Some(dealer_soft_17) = &value.dealer_soft_17 {
+            rules.dealer_soft_17 = dealer_soft_17.into();
+        }
+        if let Some(double_after_split_allowed) = value.double_after_split_allowed {
+            rules.double_after_split_allowed = double_after_split_allowed;
         }
+        if let Some(surrender) = &value.surrender {
+            rules.surrender = surrender.into();
+        }
+        if let Some(max_splits) = value.max_splits {
+            rules.max_splits = max_splits;
+        }
+        if let Some(dealer_peek) = value.dealer_peek {
+            rules.dealer_peek = dealer_peek;
+        }
+
+        rules.validate()?;
+        Ok(rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Writes a rules file to the temporary directory and loads it back
+    fn load(name: &str, content: &str) -> Result<Rules, String> {
+        let path = std::env::temp_dir().join(format!("rules-{}-{name}", std::process::id()));
+        fs::write(&path, content).unwrap();
+        let rules = parse_rules_file(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        rules
+    }
+
+    #[test]
+    fn rules_files_round_trip() {
+        for preset in Preset::all() {
+            let rules = preset.rules;
+            let toml = toml::to_string(&rules).unwrap();
+            assert_eq!(load("round-trip.toml", &toml), Ok(rules));
+            let json = serde_json::to_string(&rules).unwrap();
+            assert_eq!(load("round-trip.json", &json), Ok(rules));
+        }
+    }
+
+    #[test]
+    fn dealer_peek_defaults_to_true() {
+        let toml = r#"
+            blackjackPayout = "Ratio6to5"
+            numDecks = 2
+            dealerSoft17 = "Hit"
+            doubleAfterSplitAllowed = true
+            surrender = "Late"
+            maxSplits = 1
+        "#;
+        let expected = Rules {
+            blackjack_payout: BlackjackPayout::Ratio6to5,
+            num_decks: 2,
+            dealer_soft_17: Soft17Rule::Hit,
+            double_after_split_allowed: true,
+            surrender: SurrenderType::Late,
+            max_splits: 1,
+            dealer_peek: true,
+        };
+        assert_eq!(load("missing.toml", toml), Ok(expected));
+
+        // Unknown fields are ignored, so the default still applies
+        let json = r#"{
+            "blackjackPayout": "Ratio6to5",
+            "numDecks": 2,
+            "dealerSoft17": "Hit",
+            "doubleAfterSplitAllowed": true,
+            "surrender": "Late",
+            "maxSplits": 1,
+            "insurance": false
+        }"#;
+        assert_eq!(load("unknown.json", json), Ok(expected));
+    }
+
+    #[test]
+    fn rejects_incomplete_rules_files() {
+        let error = load("incomplete.toml", "numDecks = 2").unwrap_err();
+        assert!(error.contains("missing field `blackjackPayout`"), "{error}");
     }
 }
@@ -0,0 +1,128 @@
+use clap::ValueEnum;
+use engine::rules::{BlackjackPayout, Rules, Soft17Rule, SurrenderType};
+
+use crate::{
+    args::rules::{BlackjackPayoutArg, Soft17RuleArg, SurrenderArg},
+    utils::format::{blackjack_payout_to_string, soft_17_rule_to_string, surrender_to_string},
+};
+
+#[derive(ValueEnum, Clone, Copy, PartialEq)]
+pub enum SweepParam {
+    NumDecks,
+    BlackjackPayout,
+    #[clap(name = "dealer-soft-17")]
+    DealerSoft17,
+    Surrender,
+}
+
+impl SweepParam {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SweepParam::NumDecks => "num_decks",
+            SweepParam::BlackjackPayout => "blackjack_payout",
+            SweepParam::DealerSoft17 => "dealer_soft_17",
+            SweepParam::Surrender => "surrender",
+        }
+    }
+
+    fn all_values(&self) -> Vec<SweepValue> {
+        match self {
+            SweepParam::NumDecks => (1..=8).map(SweepValue::NumDecks).collect(),
+            SweepParam::BlackjackPayout => vec![
+                SweepValue::BlackjackPayout(BlackjackPayout::Ratio3to2),
+                SweepValue::BlackjackPayout(BlackjackPayout::Ratio6to5),
+            ],
+            SweepParam::DealerSoft17 => vec![
+                SweepValue::DealerSoft17(Soft17Rule::Stand),
+                SweepValue::DealerSoft17(Soft17Rule::Hit),
+            ],
+            SweepParam::Surrender => vec![
+                SweepValue::Surrender(SurrenderType::None),
+                SweepValue::Surrender(SurrenderType::Late),
+                SweepValue::Surrender(SurrenderType::Early),
+            ],
+        }
+    }
+
+    fn parse_value(&self, value: &str) -> Result<SweepValue, String> {
+        match self {
+            SweepParam::NumDecks => value
+                .parse()
+                .map(SweepValue::NumDecks)
+                .map_err(|_| format!("Invalid number of decks {value}")),
+            SweepParam::BlackjackPayout => BlackjackPayoutArg::from_str(value, true)
+                .map(|arg| SweepValue::BlackjackPayout((&arg).into())),
+            SweepParam::DealerSoft17 => Soft17RuleArg::from_str(value, true)
+                .map(|arg| SweepValue::DealerSoft17((&arg).into())),
+            SweepParam::Surrender => {
+                SurrenderArg::from_str(value, true).map(|arg| SweepValue::Surrender((&arg).into()))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum SweepValue {
+    NumDecks(u8),
+    BlackjackPayout(BlackjackPayout),
+    DealerSoft17(Soft17Rule),
+    Surrender(SurrenderType),
+}
+
+impl SweepValue {
+    pub fn apply(&self, rules: &mut Rules) {
+        match self {
+            SweepValue::NumDecks(num_decks) => rules.num_decks = *num_decks,
+            SweepValue::BlackjackPayout(payout) => rules.blackjack_payout = *payout,
+            SweepValue::DealerSoft17(rule) => rules.dealer_soft_17 = *rule,
+            SweepValue::Surrender(surrender) => rules.surrender = *surrender,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            SweepValue::NumDecks(num_decks) => num_decks.to_string(),
+            SweepValue::BlackjackPayout(payout) => blackjack_payout_to_string(payout),
+            SweepValue::DealerSoft17(rule) => soft_17_rule_to_string(rule),
+            SweepValue::Surrender(surrender) => surrender_to_string(surrender),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SweepArg {
+    pub param: SweepParam,
+    pub values: Vec<SweepValue>,
+}
+
+// Parses `param` or `param=values`, where values is a comma separated list
+// and decks also accept an inclusive `from..to` range
+pub fn parse_sweep(input: &str) -> Result<SweepArg, String> {
+    let (name, values) = match input.split_once('=') {
+        Some((name, values)) => (name, Some(values)),
+        None => (input, None),
+    };
+    let param = SweepParam::from_str(name, true)?;
+
+    let values = match values {
+        None => param.all_values(),
+        Some(values) if param == SweepParam::NumDecks && values.contains("..") => {
+            let (from, to) = values.split_once("..").unwrap();
+            let from: u8 = from
+                .parse()
+                .map_err(|_| format!("Invalid range start {from}"))?;
+            let to: u8 = to.parse().map_err(|_| format!("Invalid range end {to}"))?;
+            (from..=to).map(SweepValue::NumDecks).collect()
+        }
+        Some(values) => values
+            .split(',')
+            .map(|value| param.parse_value(value))
+            .collect::<Result<_, _>>()?,
+    };
+
+    if values.is_empty() {
+        return Err(format!("No values to sweep for {name}"));
+    }
+
+    Ok(SweepArg { param, values })
+}
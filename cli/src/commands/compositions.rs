@@ -0,0 +1,147 @@
+use anyhow::Result;
+use clap::Args;
+use engine::{
+    card::{Card, Rank},
+    composition::CompositionAnalysis,
+    options::GeneratorOptions,
+    rules::Rules,
+    shoe::{CountShoe, HandType},
+    strategy::StrategyGenerator,
+};
+use serde::Serialize;
+
+use crate::{
+    args::{precision::PrecisionArgs, rules::RulesArgs},
+    commands::table::{PrintFormat, Report},
+    utils::{
+        format::{action_to_string, hand_to_string},
+        parse::parse_card_value,
+    },
+};
+
+#[derive(Args)]
+pub struct CompositionsArgs {
+    /// Player total
+    player: u8,
+
+    dealer: String,
+
+    /// Analyze a soft total instead of a hard one
+    #[arg(long)]
+    soft: bool,
+
+    /// Largest number of cards in a listed composition
+    #[arg(long, default_value_t = 21)]
+    max_cards: u8,
+
+    #[arg(long, short, default_value_t = PrintFormat::Markdown)]
+    format: PrintFormat,
+
+    #[command(flatten)]
+    rules: RulesArgs,
+
+    #[command(flatten)]
+    precision: PrecisionArgs,
+}
+
+#[derive(Serialize)]
+struct CompositionRow {
+    cards: String,
+    probability: f64,
+    action: String,
+    ev: f64,
+    flipped: bool,
+}
+
+#[derive(Serialize)]
+struct CompositionReport {
+    total: String,
+    dealer: u8,
+    action: String,
+    ev: f64,
+    compositions: Vec<CompositionRow>,
+}
+
+pub fn cmd_compositions(args: &CompositionsArgs) -> Result<()> {
+    let rules = Rules::try_from(&args.rules)?;
+    let shoe = CountShoe::new(rules.num_decks)?;
+    let options = GeneratorOptions::try_from(&args.precision)?;
+    let mut strategy = StrategyGenerator::with_options(rules, shoe, options)?;
+
+    let dealer_value = parse_card_value(&args.dealer)?;
+    let dealer_upcard = Card::from_rank(Rank::from_value(dealer_value)?);
+    let hand_type = if args.soft {
+        HandType::Soft
+    } else {
+        HandType::Hard
+    };
+
+    let analysis =
+        strategy.analyze_compositions(args.player, hand_type, dealer_upcard, args.max_cards)?;
+    let report = build_report(&analysis);
+
+    args.format.echo_options(&options);
+    args.format.print_report(&report)
+}
+
+fn build_report(analysis: &CompositionAnalysis) -> CompositionReport {
+    let hand_type = match analysis.hand_type {
+        HandType::Hard => "hard",
+        HandType::Soft => "soft",
+    };
+
+    CompositionReport {
+        total: format!("{} {}", hand_type, analysis.player_value),
+        dealer: analysis.dealer_upcard.rank.value(),
+        action: action_to_string(&analysis.total.action),
+        ev: analysis.total.ev,
+        compositions: analysis
+            .compositions
+            .iter()
+            .map(|composition| CompositionRow {
+                cards: hand_to_string(&composition.hand),
+                probability: composition.weight,
+                action: action_to_string(&composition.value.action),
+                ev: composition.value.ev,
+                flipped: composition.flipped,
+            })
+            .collect(),
+    }
+}
+
+impl Report for CompositionReport {
+    fn print_markdown(&self) {
+        println!("Player total: {}", self.total);
+        println!("Dealer value: {}", self.dealer);
+        println!("Table action: {} (ev = {:.4})\n", self.action, self.ev);
+        println!("| Cards                | Probability | Action | EV      | Flipped |");
+        println!("|----------------------|------------:|--------|--------:|---------|");
+        for row in &self.compositions {
+            println!(
+                "| {:<20} | {:>11.8} | {:<6} | {:>+7.4} | {:<7} |",
+                row.cards,
+                row.probability,
+                row.action,
+                row.ev,
+                if row.flipped { "yes" } else { "" }
+            );
+        }
+
+        let flipped = self.compositions.iter().filter(|row| row.flipped).count();
+        println!(
+            "\n{} of {} compositions play differently from the table",
+            flipped,
+            self.compositions.len()
+        );
+    }
+
+    fn print_csv(&self) {
+        println!("cards,probability,action,ev,flipped");
+        for row in &self.compositions {
+            println!(
+                "\"{}\",{},{},{},{}",
+                row.cards, row.probability, row.action, row.ev, row.flipped
+            );
+        }
+    }
+}
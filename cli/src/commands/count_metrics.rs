@@ -0,0 +1,156 @@
+use anyhow::Result;
+use clap::Args;
+use engine::{
+    counting::CountingSystem, options::GeneratorOptions, removal::RemovalEffects, rules::Rules,
+};
+use serde::Serialize;
+
+use crate::{
+    args::{
+        counting::{parse_system, parse_tags},
+        precision::PrecisionArgs,
+        rules::RulesArgs,
+    },
+    commands::table::{PrintFormat, Report},
+    utils::format::card_value_to_string,
+};
+
+#[derive(Args)]
+pub struct CountMetricsArgs {
+    #[arg(long, short, default_value_t = PrintFormat::Markdown)]
+    format: PrintFormat,
+
+    /// Built-in counting system to rate, every system is rated when none is given
+    #[arg(long = "system", value_parser = parse_system)]
+    systems: Vec<CountingSystem>,
+
+    /// Custom counting system as ten comma separated tags from two to ace
+    #[arg(long = "tags", value_parser = parse_tags, allow_hyphen_values = true)]
+    custom_systems: Vec<CountingSystem>,
+
+    #[command(flatten)]
+    rules: RulesArgs,
+
+    #[command(flatten)]
+    precision: PrecisionArgs,
+}
+
+#[derive(Serialize)]
+struct RankEffect {
+    rank: String,
+    effect: f64,
+}
+
+#[derive(Serialize)]
+struct SystemRow {
+    system: String,
+    tags: Vec<f64>,
+    balanced: bool,
+    betting_correlation: f64,
+    playing_efficiency: f64,
+    insurance_correlation: f64,
+}
+
+#[derive(Serialize)]
+struct MetricsReport {
+    base_ev: f64,
+    effects_of_removal: Vec<RankEffect>,
+    systems: Vec<SystemRow>,
+}
+
+pub fn cmd_count_metrics(args: &CountMetricsArgs) -> Result<()> {
+    let rules = Rules::try_from(&args.rules)?;
+    let options = GeneratorOptions::try_from(&args.precision)?;
+    let effects = RemovalEffects::compute(rules, options)?;
+
+    let mut systems = args.systems.clone();
+    systems.extend(args.custom_systems.iter().copied());
+    if systems.is_empty() {
+        systems.extend(CountingSystem::all().copied());
+    }
+
+    let report = MetricsReport {
+        base_ev: effects.base_ev,
+        effects_of_removal: effects
+            .effects
+            .iter()
+            .enumerate()
+            .map(|(index, effect)| RankEffect {
+                // Effects are indexed by rank from two to ace
+                rank: card_value_to_string(index as u8 + 2),
+                effect: *effect,
+            })
+            .collect(),
+        systems: systems
+            .iter()
+            .map(|system| {
+                let metrics = system.metrics(&effects);
+                SystemRow {
+                    system: String::from(system.name),
+                    tags: system.tags.to_vec(),
+                    balanced: system.is_balanced(),
+                    betting_correlation: metrics.betting_correlation,
+                    playing_efficiency: metrics.playing_efficiency,
+                    insurance_correlation: metrics.insurance_correlation,
+                }
+            })
+            .collect(),
+    };
+
+    args.format.echo_options(&options);
+    args.format.print_report(&report)
+}
+
+fn tags_to_string(tags: &[f64]) -> String {
+    tags.iter()
+        .map(|tag| tag.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Report for MetricsReport {
+    fn print_markdown(&self) {
+        println!("Base expected return: {:.4}%\n", self.base_ev * 100.0);
+        println!("| Rank | Effect of removal |");
+        println!("|------|------------------:|");
+        for effect in &self.effects_of_removal {
+            println!("| {:<4} | {:>+16.4}% |", effect.rank, effect.effect * 100.0);
+        }
+
+        println!();
+        println!(
+            "| System       | Tags                                  | Balanced | BC     | PE     | IC     |"
+        );
+        println!(
+            "|--------------|---------------------------------------|----------|-------:|-------:|-------:|"
+        );
+        for system in &self.systems {
+            println!(
+                "| {:<12} | {:<37} | {:<8} | {:>6.3} | {:>6.3} | {:>6.3} |",
+                system.system,
+                tags_to_string(&system.tags),
+                if system.balanced { "yes" } else { "no" },
+                system.betting_correlation,
+                system.playing_efficiency,
+                system.insurance_correlation
+            );
+        }
+    }
+
+    fn print_csv(&self) {
+        println!(
+            "system,tags,balanced,betting_correlation,playing_efficiency,insurance_correlation"
+        );
+        for system in &self.systems {
+            println!(
+                "{},\"{}\",{},{},{},{}",
+                system.system,
+                tags_to_string(&system.tags),
+                system.balanced,
+                system.betting_correlation,
+                system.playing_efficiency,
+                system.insurance_correlation
+            );
+        }
+    }
+}
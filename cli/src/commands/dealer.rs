@@ -0,0 +1,134 @@
+use anyhow::Result;
+use clap::Args;
+use engine::{
+    dealer::{DealerOutcomes, DealerTable},
+    options::GeneratorOptions,
+    rules::Rules,
+    shoe::{CountShoe, InfiniteShoe},
+    strategy::StrategyGenerator,
+};
+use serde::Serialize;
+
+use crate::{
+    args::{precision::PrecisionArgs, rules::RulesArgs},
+    commands::table::{PrintFormat, Report},
+    utils::format::card_value_to_string,
+};
+
+#[derive(Args)]
+pub struct DealerArgs {
+    #[arg(long, short, default_value_t = PrintFormat::Markdown)]
+    format: PrintFormat,
+
+    /// Draw from an infinite shoe instead of the number of decks in the rules
+    #[arg(long)]
+    infinite: bool,
+
+    #[command(flatten)]
+    rules: RulesArgs,
+
+    #[command(flatten)]
+    precision: PrecisionArgs,
+}
+
+#[derive(Serialize)]
+struct DealerRow {
+    upcard: String,
+    seventeen: f64,
+    eighteen: f64,
+    nineteen: f64,
+    twenty: f64,
+    twenty_one: f64,
+    natural: f64,
+    bust: f64,
+}
+
+// One row per upcard, then the outcomes over every upcard
+#[derive(Serialize)]
+#[serde(transparent)]
+struct DealerReport {
+    rows: Vec<DealerRow>,
+}
+
+impl DealerRow {
+    fn new(upcard: String, outcomes: &DealerOutcomes) -> Self {
+        Self {
+            upcard,
+            seventeen: outcomes.seventeen,
+            eighteen: outcomes.eighteen,
+            nineteen: outcomes.nineteen,
+            twenty: outcomes.twenty,
+            twenty_one: outcomes.twenty_one,
+            natural: outcomes.natural,
+            bust: outcomes.bust,
+        }
+    }
+
+    fn values(&self) -> [f64; 7] {
+        [
+            self.seventeen,
+            self.eighteen,
+            self.nineteen,
+            self.twenty,
+            self.twenty_one,
+            self.natural,
+            self.bust,
+        ]
+    }
+}
+
+pub fn cmd_dealer(args: &DealerArgs) -> Result<()> {
+    let rules = Rules::try_from(&args.rules)?;
+    let options = GeneratorOptions::try_from(&args.precision)?;
+    let table = if args.infinite {
+        StrategyGenerator::with_options(rules, InfiniteShoe::new(), options)?.dealer_table()
+    } else {
+        let shoe = CountShoe::new(rules.num_decks)?;
+        StrategyGenerator::with_options(rules, shoe, options)?.dealer_table()
+    };
+    let report = DealerReport {
+        rows: build_rows(&table),
+    };
+
+    args.format.echo_options(&options);
+    args.format.print_report(&report)
+}
+
+fn build_rows(table: &DealerTable) -> Vec<DealerRow> {
+    let mut rows: Vec<_> = table
+        .upcards
+        .iter()
+        .map(|upcard| DealerRow::new(card_value_to_string(upcard.upcard), &upcard.outcomes))
+        .collect();
+    rows.push(DealerRow::new("all".to_string(), &table.overall));
+    rows
+}
+
+impl Report for DealerReport {
+    fn print_markdown(&self) {
+        println!(
+            "| Upcard | 17      | 18      | 19      | 20      | 21      | Natural | Bust    |"
+        );
+        println!(
+            "|--------|--------:|--------:|--------:|--------:|--------:|--------:|--------:|"
+        );
+        for row in &self.rows {
+            print!("| {:<6} |", row.upcard);
+            for value in row.values() {
+                print!(" {:>6.2}% |", value * 100.0);
+            }
+            println!();
+        }
+    }
+
+    fn print_csv(&self) {
+        println!("upcard,17,18,19,20,21,natural,bust");
+        for row in &self.rows {
+            print!("{}", row.upcard);
+            for value in row.values() {
+                print!(",{}", value);
+            }
+            println!();
+        }
+    }
+}
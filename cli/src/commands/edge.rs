@@ -0,0 +1,34 @@
+use anyhow::Result;
+use clap::Args;
+use engine::{
+    options::GeneratorOptions, rules::Rules, shoe::CountShoe, strategy::StrategyGenerator,
+};
+
+use crate::{
+    args::{precision::PrecisionArgs, rules::RulesArgs},
+    utils::format::options_to_string,
+};
+
+#[derive(Args)]
+pub struct EdgeArgs {
+    #[command(flatten)]
+    rules: RulesArgs,
+
+    #[command(flatten)]
+    precision: PrecisionArgs,
+}
+
+pub fn cmd_edge(args: &EdgeArgs) -> Result<()> {
+    let rules = Rules::try_from(&args.rules)?;
+    let shoe = CountShoe::new(rules.num_decks)?;
+    let options = GeneratorOptions::try_from(&args.precision)?;
+    let mut generator = StrategyGenerator::with_options(rules, shoe, options)?;
+
+    let ev = generator.expected_return();
+
+    println!("Solver: {}", options_to_string(&options));
+    println!("Expected return: {:.4}%", ev * 100.0);
+    println!("House edge: {:.4}%", -ev * 100.0);
+
+    Ok(())
+}
@@ -2,14 +2,24 @@ use clap::Args;
 use colored::{ColoredString, Colorize};
 use engine::{
     card::{Card, Rank},
+    distribution::OutcomeDistribution,
+    error::Error,
+    exact::{ExactGenerator, Rational},
     hand::Hand,
-    shoe::CountShoe,
+    options::GeneratorOptions,
+    rules::Rules,
+    shoe::{CountShoe, Shoe},
     strategy::StrategyGenerator,
 };
-use regex::Regex;
 
-use crate::{args::rules::RulesArgs, utils::format::action_to_long_colored_string};
-use anyhow::{Result, anyhow};
+use crate::{
+    args::{cache::CacheArgs, precision::PrecisionArgs, rules::RulesArgs},
+    utils::{
+        format::{action_to_long_colored_string, cache_stats_to_string, options_to_string},
+        parse::parse_card_value,
+    },
+};
+use anyhow::Result;
 
 #[derive(Args)]
 pub struct HandArgs {
@@ -17,31 +27,57 @@ pub struct HandArgs {
 
     dealer: String,
 
+    /// Show the distribution of outcomes of each action
+    #[arg(long)]
+    distribution: bool,
+
+    /// Also solve the hand with exact fractions and no pruning, only practical for few decks.
+    /// The maximum depth and split mode apply to it as well
+    #[arg(long)]
+    exact: bool,
+
     #[command(flatten)]
     rules: RulesArgs,
+
+    #[command(flatten)]
+    precision: PrecisionArgs,
+
+    #[command(flatten)]
+    cache: CacheArgs,
 }
 
 pub fn cmd_hand(args: &HandArgs) -> Result<()> {
-    let shoe = CountShoe::new(args.rules.num_decks);
-    let mut strategy = StrategyGenerator::new((&args.rules).into(), shoe);
+    let rules = Rules::try_from(&args.rules)?;
+    let shoe = CountShoe::new(rules.num_decks)?;
+    let options = GeneratorOptions::try_from(&args.precision)?;
+    let mut strategy = StrategyGenerator::with_options(rules, shoe, options)?;
+    args.cache.load(&mut strategy);
 
-    let dealer_value = parse_value(&args.dealer)?;
-    let dealer_upcard = Card::from_rank(Rank::from_value(dealer_value));
+    let dealer_value = parse_card_value(&args.dealer)?;
+    let dealer_upcard = Card::from_rank(Rank::from_value(dealer_value)?);
 
     let parts = args.player.split(":");
     let mut player_hand = Hand::new();
-    let mut cards = Vec::<Card>::new();
     for part in parts {
-        let player_value = parse_value(&part.to_string())?;
-        let card = Card::from_rank(Rank::from_value(player_value));
+        let player_value = parse_card_value(part)?;
+        let card = Card::from_rank(Rank::from_value(player_value)?);
         player_hand.add_card(&card);
-        cards.push(card);
     }
 
+    // The solver only deals the cards left in the shoe, so the hand must come from it
+    let mut shoe = strategy.shoe().clone();
+    for card in player_hand.cards().chain([dealer_upcard]) {
+        if shoe.count(card.rank) == 0 {
+            return Err(Error::CardNotInShoe(card.rank.value()).into());
+        }
+        shoe.remove_card(&card);
+    }
+
+    println!("Solver: {}", options_to_string(&options));
     println!("Dealer value: {}", dealer_value);
 
-    let is_pair = cards.len() == 2 && cards[0].rank == cards[1].rank;
-    let evs = strategy.eval_round(player_hand, dealer_upcard, is_pair);
+    let (evs, error) =
+        strategy.eval_round_with_error(player_hand, dealer_upcard, player_hand.is_pair());
 
     println!("Expected values:");
     println!("  Hit: {}", colorize_ev(evs.hit));
@@ -53,6 +89,40 @@ pub fn cmd_hand(args: &HandArgs) -> Result<()> {
     if let Some(surrender_ev) = evs.surrender {
         println!("  Surrender: {}", colorize_ev(surrender_ev));
     }
+    println!("Pruning error bound: ±{error:.2e}");
+
+    if args.exact {
+        let shoe = CountShoe::new(rules.num_decks)?;
+        let mut exact = ExactGenerator::with_options(rules, shoe, options)?;
+        let exact_evs = exact.eval_round(player_hand, dealer_upcard, player_hand.is_pair())?;
+
+        println!("\nExact values:");
+        print_exact("Hit", exact_evs.hit, evs.hit);
+        print_exact("Stand", exact_evs.stand, evs.stand);
+        print_exact("Double", exact_evs.double, evs.double);
+        if let (Some(exact_ev), Some(ev)) = (exact_evs.split, evs.split) {
+            print_exact("Split", exact_ev, ev);
+        }
+        if let (Some(exact_ev), Some(ev)) = (exact_evs.surrender, evs.surrender) {
+            print_exact("Surrender", exact_ev, ev);
+        }
+    }
+
+    if args.distribution {
+        let distributions =
+            strategy.eval_round_distributions(player_hand, dealer_upcard, player_hand.is_pair());
+
+        println!("\nOutcome distributions:");
+        print_distribution("Hit", &distributions.hit);
+        print_distribution("Stand", &distributions.stand);
+        print_distribution("Double", &distributions.double);
+        if let Some(split) = &distributions.split {
+            print_distribution("Split", split);
+        }
+        if let Some(surrender) = &distributions.surrender {
+            print_distribution("Surrender", surrender);
+        }
+    }
 
     let (best_action, ev) = evs.best();
 
@@ -62,7 +132,29 @@ pub fn cmd_hand(args: &HandArgs) -> Result<()> {
         ev
     );
 
-    Ok(())
+    if args.cache.cache_stats {
+        println!("\n{}", cache_stats_to_string(&strategy.cache_stats()));
+    }
+    args.cache.save(&strategy)
+}
+
+fn print_distribution(label: &str, distribution: &OutcomeDistribution) {
+    println!(
+        "  {label} (variance = {:.4}, standard deviation = {:.4}):",
+        distribution.variance(),
+        distribution.variance().sqrt()
+    );
+    for (outcome, probability) in distribution.iter() {
+        println!("    {:>+5.1}: {:>8.4}%", outcome, probability * 100.0);
+    }
+}
+
+fn print_exact(label: &str, exact_ev: Rational, ev: f64) {
+    println!(
+        "  {label}: {} = {exact_ev} (difference {:.2e})",
+        colorize_ev(exact_ev.to_f64()),
+        (exact_ev.to_f64() - ev).abs()
+    );
 }
 
 fn colorize_ev(value: f64) -> ColoredString {
@@ -73,16 +165,3 @@ fn colorize_ev(value: f64) -> ColoredString {
         string_value.green()
     }
 }
-
-fn parse_value(value_string: &String) -> Result<u8> {
-    let re = Regex::new("[2-9JQKA]|10").unwrap();
-    if !re.is_match(value_string) {
-        Err(anyhow!("Invalid card value {}", value_string))
-    } else {
-        Ok(match value_string.as_str() {
-            "A" => 11,
-            "J" | "Q" | "K" | "10" => 10,
-            _ => value_string.parse()?,
-        })
-    }
-}
@@ -0,0 +1,197 @@
+use anyhow::Result;
+use clap::Args;
+use engine::{
+    options::GeneratorOptions,
+    rules::{BlackjackPayout, Rules, Soft17Rule, SurrenderType},
+    shoe::CountShoe,
+    strategy::StrategyGenerator,
+};
+use serde::Serialize;
+
+use crate::{
+    args::{precision::PrecisionArgs, rules::RulesArgs},
+    commands::table::{PrintFormat, Report},
+    utils::format::{blackjack_payout_to_string, soft_17_rule_to_string, surrender_to_string},
+};
+
+#[derive(Args)]
+pub struct ImpactArgs {
+    #[arg(long, short, default_value_t = PrintFormat::Markdown)]
+    format: PrintFormat,
+
+    #[command(flatten)]
+    rules: RulesArgs,
+
+    #[command(flatten)]
+    precision: PrecisionArgs,
+}
+
+#[derive(Serialize)]
+struct RuleImpact {
+    rule: String,
+    base: String,
+    changed: String,
+    ev: f64,
+    delta: f64,
+}
+
+#[derive(Serialize)]
+struct ImpactReport {
+    base_ev: f64,
+    impacts: Vec<RuleImpact>,
+}
+
+pub fn cmd_impact(args: &ImpactArgs) -> Result<()> {
+    let rules = Rules::try_from(&args.rules)?;
+    let options = GeneratorOptions::try_from(&args.precision)?;
+    let base_ev = expected_return(rules, options)?;
+
+    let impacts = rule_variations(&rules)
+        .into_iter()
+        .map(|(rule, base, changed, variation)| {
+            let ev = expected_return(variation, options)?;
+            Ok(RuleImpact {
+                rule: rule.into(),
+                base,
+                changed,
+                ev,
+                delta: ev - base_ev,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    let report = ImpactReport { base_ev, impacts };
+
+    args.format.echo_options(&options);
+    args.format.print_report(&report)
+}
+
+fn expected_return(rules: Rules, options: GeneratorOptions) -> Result<f64> {
+    let shoe = CountShoe::new(rules.num_decks)?;
+    Ok(StrategyGenerator::with_options(rules, shoe, options)?.expected_return())
+}
+
+fn rule_variations(rules: &Rules) -> Vec<(&'static str, String, String, Rules)> {
+    let mut variations = Vec::new();
+
+    let blackjack_payout = match rules.blackjack_payout {
+        BlackjackPayout::Ratio3to2 => BlackjackPayout::Ratio6to5,
+        BlackjackPayout::Ratio6to5 => BlackjackPayout::Ratio3to2,
+    };
+    variations.push((
+        "blackjack_payout",
+        blackjack_payout_to_string(&rules.blackjack_payout),
+        blackjack_payout_to_string(&blackjack_payout),
+        Rules {
+            blackjack_payout,
+            ..*rules
+        },
+    ));
+
+    for num_decks in [1, 2, 4, 6, 8] {
+        if num_decks != rules.num_decks {
+            variations.push((
+                "num_decks",
+                rules.num_decks.to_string(),
+                num_decks.to_string(),
+                Rules {
+                    num_decks,
+                    ..*rules
+                },
+            ));
+        }
+    }
+
+    let dealer_soft_17 = match rules.dealer_soft_17 {
+        Soft17Rule::Hit => Soft17Rule::Stand,
+        Soft17Rule::Stand => Soft17Rule::Hit,
+    };
+    variations.push((
+        "dealer_soft_17",
+        soft_17_rule_to_string(&rules.dealer_soft_17),
+        soft_17_rule_to_string(&dealer_soft_17),
+        Rules {
+            dealer_soft_17,
+            ..*rules
+        },
+    ));
+
+    variations.push((
+        "double_after_split_allowed",
+        rules.double_after_split_allowed.to_string(),
+        (!rules.double_after_split_allowed).to_string(),
+        Rules {
+            double_after_split_allowed: !rules.double_after_split_allowed,
+            ..*rules
+        },
+    ));
+
+    for surrender in [
+        SurrenderType::None,
+        SurrenderType::Early,
+        SurrenderType::Late,
+    ] {
+        if surrender != rules.surrender {
+            variations.push((
+                "surrender",
+                surrender_to_string(&rules.surrender),
+                surrender_to_string(&surrender),
+                Rules {
+                    surrender,
+                    ..*rules
+                },
+            ));
+        }
+    }
+
+    variations.push((
+        "dealer_peek",
+        rules.dealer_peek.to_string(),
+        (!rules.dealer_peek).to_string(),
+        Rules {
+            dealer_peek: !rules.dealer_peek,
+            ..*rules
+        },
+    ));
+
+    let max_splits = if rules.max_splits > 1 { 1 } else { 3 };
+    variations.push((
+        "max_splits",
+        rules.max_splits.to_string(),
+        max_splits.to_string(),
+        Rules {
+            max_splits,
+            ..*rules
+        },
+    ));
+
+    variations
+}
+
+impl Report for ImpactReport {
+    fn print_markdown(&self) {
+        println!("Base expected return: {:.4}%\n", self.base_ev * 100.0);
+        println!("| Rule                       | Base  | Changed | EV        | Delta     |");
+        println!("|----------------------------|-------|---------|----------:|----------:|");
+        for impact in &self.impacts {
+            println!(
+                "| {:<26} | {:<5} | {:<7} | {:>+8.4}% | {:>+8.4}% |",
+                impact.rule,
+                impact.base,
+                impact.changed,
+                impact.ev * 100.0,
+                impact.delta * 100.0
+            );
+        }
+    }
+
+    fn print_csv(&self) {
+        println!("rule,base,changed,ev,delta");
+        for impact in &self.impacts {
+            println!(
+                "{},{},{},{},{}",
+                impact.rule, impact.base, impact.changed, impact.ev, impact.delta
+            );
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use anyhow::Result;
+use clap::Args;
+use engine::{
+    counting::CountingSystem,
+    indices::{IndexChart, IndexGenerator, RiskAversion},
+    options::GeneratorOptions,
+    rules::Rules,
+    strategy::DecisionTable,
+};
+use serde::Serialize;
+
+use crate::{
+    args::{
+        counting::{parse_system, parse_tags},
+        precision::PrecisionArgs,
+        rules::RulesArgs,
+    },
+    commands::table::{PrintFormat, Report, TableType},
+    utils::format::{action_to_string, card_value_to_string},
+};
+
+#[derive(Args)]
+pub struct IndicesArgs {
+    #[arg(long, short, default_value_t = PrintFormat::Markdown)]
+    format: PrintFormat,
+
+    /// Built-in counting system the indices are for. Indices of unbalanced systems are in
+    /// balanced equivalent true counts, measured from the running count of a neutral shoe
+    #[arg(long, value_parser = parse_system, default_value = "hi-lo")]
+    system: CountingSystem,
+
+    /// Custom counting system as ten comma separated tags from two to ace
+    #[arg(long, value_parser = parse_tags, allow_hyphen_values = true)]
+    tags: Option<CountingSystem>,
+
+    #[arg(long, default_value_t = -6, allow_hyphen_values = true)]
+    min_count: i8,
+
+    #[arg(long, default_value_t = 6)]
+    max_count: i8,
+
+    /// Bankroll in units, adds risk averse indices for the given bet size
+    #[arg(long, requires = "bet")]
+    bankroll: Option<f64>,
+
+    /// Bet size in units, used with the bankroll for risk averse indices
+    #[arg(long, requires = "bankroll")]
+    bet: Option<f64>,
+
+    #[command(flatten)]
+    rules: RulesArgs,
+
+    #[command(flatten)]
+    precision: PrecisionArgs,
+}
+
+#[derive(Serialize)]
+struct IndexRow {
+    table: String,
+    player: String,
+    dealer: String,
+    basic_action: String,
+    action: String,
+    true_count: f64,
+    risk_averse_true_count: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct IndexReport {
+    system: String,
+    #[serde(skip)]
+    description: String,
+    #[serde(skip)]
+    risk_averse: bool,
+    insurance: Option<f64>,
+    indices: Vec<IndexRow>,
+}
+
+pub fn cmd_indices(args: &IndicesArgs) -> Result<()> {
+    let rules = Rules::try_from(&args.rules)?;
+    let system = args.tags.unwrap_or(args.system);
+    let options = GeneratorOptions::try_from(&args.precision)?;
+    let mut generator = IndexGenerator::new(rules, system, args.min_count..=args.max_count)?
+        .with_options(options)?;
+    if let (Some(bankroll), Some(bet)) = (args.bankroll, args.bet) {
+        generator = generator.with_risk_aversion(RiskAversion { bankroll, bet });
+    }
+    let chart = generator.generate()?;
+
+    args.format.echo_options(&options);
+    args.format
+        .print_report(&build_report(&system, &chart, args.bankroll.is_some()))
+}
+
+fn table_type(table: DecisionTable) -> TableType {
+    match table {
+        DecisionTable::Hard => TableType::Hard,
+        DecisionTable::Soft => TableType::Soft,
+        DecisionTable::Pair => TableType::Pair,
+    }
+}
+
+fn table_name(table: DecisionTable) -> &'static str {
+    match table {
+        DecisionTable::Hard => "hard",
+        DecisionTable::Soft => "soft",
+        DecisionTable::Pair => "pair",
+    }
+}
+
+fn index_label(index: &IndexRow) -> String {
+    let label = format!("{}{:+.0}", index.action, index.true_count);
+    match index.risk_averse_true_count {
+        Some(true_count) => format!("{label} ({true_count:+.0})"),
+        None => label,
+    }
+}
+
+fn build_report(system: &CountingSystem, chart: &IndexChart, risk_averse: bool) -> IndexReport {
+    IndexReport {
+        system: String::from(system.name),
+        description: String::from(system.description),
+        risk_averse,
+        insurance: chart.insurance,
+        indices: chart
+            .indices
+            .iter()
+            .map(|index| IndexRow {
+                table: String::from(table_name(index.table)),
+                player: table_type(index.table).get_label(index.player_value),
+                dealer: card_value_to_string(index.dealer_value),
+                basic_action: action_to_string(&index.basic_action),
+                action: action_to_string(&index.action),
+                true_count: index.true_count,
+                risk_averse_true_count: index.risk_averse_true_count,
+            })
+            .collect(),
+    }
+}
+
+impl Report for IndexReport {
+    fn print_markdown(&self) {
+        println!("System: {}", self.description);
+        match self.insurance {
+            Some(true_count) => println!("Insurance: take at {true_count:+.1}"),
+            None => println!("Insurance: never"),
+        }
+
+        for table in DecisionTable::ALL {
+            let indices: Vec<_> = self
+                .indices
+                .iter()
+                .filter(|index| index.table == table_name(table))
+                .collect();
+            let mut players: Vec<_> = indices.iter().map(|index| &index.player).collect();
+            players.dedup();
+            if players.is_empty() {
+                continue;
+            }
+
+            println!("\n\n# {}\n", table_name(table).to_uppercase());
+            print!("| Hand \\ Dealer |");
+            let width = if self.risk_averse { 11 } else { 5 };
+            for dealer_value in 2..=11 {
+                print!(" {:<width$} |", card_value_to_string(dealer_value));
+            }
+            println!();
+
+            print!("|---------------|");
+            for _ in 2..=11 {
+                print!(":{}:|", "-".repeat(width));
+            }
+            println!();
+
+            for player in players {
+                print!("| {player:<13} |");
+                for dealer_value in 2..=11 {
+                    let dealer = card_value_to_string(dealer_value);
+                    // Cells show the index closest to the neutral count
+                    let cell = indices
+                        .iter()
+                        .filter(|index| index.player == *player && index.dealer == dealer)
+                        .min_by(|a, b| a.true_count.abs().total_cmp(&b.true_count.abs()))
+                        .map(|index| index_label(index))
+                        .unwrap_or_default();
+                    print!(" {cell:<width$} |");
+                }
+                println!();
+            }
+        }
+    }
+
+    fn print_csv(&self) {
+        println!("table,player,dealer,basic_action,action,true_count,risk_averse_true_count");
+        if let Some(true_count) = self.insurance {
+            println!("insurance,,A,,,{true_count},");
+        }
+        for index in &self.indices {
+            println!(
+                "{},\"{}\",{},{},{},{},{}",
+                index.table,
+                index.player,
+                index.dealer,
+                index.basic_action,
+                index.action,
+                index.true_count,
+                index
+                    .risk_averse_true_count
+                    .map(|true_count| true_count.to_string())
+                    .unwrap_or_default()
+            );
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::io::{self, BufRead};
+
+use anyhow::{Result, anyhow};
+use clap::Args;
+use engine::{
+    card::{Card, Rank},
+    hand::Hand,
+    options::GeneratorOptions,
+    rules::Rules,
+    shoe::CountShoe,
+    strategy::StrategyGenerator,
+};
+
+use crate::{
+    args::{precision::PrecisionArgs, rules::RulesArgs},
+    utils::{
+        format::{action_to_long_colored_string, options_to_string},
+        parse::parse_card_value,
+    },
+};
+
+#[derive(Args)]
+#[command(
+    about = "Follows a live shoe from stdin, one line per event. Cards leaving the shoe are \
+             given as `5:K:A` and hands to solve as `10:6 10`. The cards of a solved hand are \
+             only taken out of the shoe for that solve, they are reported once the round is \
+             over"
+)]
+pub struct LiveArgs {
+    #[command(flatten)]
+    rules: RulesArgs,
+
+    #[command(flatten)]
+    precision: PrecisionArgs,
+}
+
+pub fn cmd_live(args: &LiveArgs) -> Result<()> {
+    let rules = Rules::try_from(&args.rules)?;
+    let shoe = CountShoe::new(rules.num_decks)?;
+    let options = GeneratorOptions::try_from(&args.precision)?;
+    let mut strategy = StrategyGenerator::with_options(rules, shoe, options)?;
+
+    println!("Solver: {}", options_to_string(&options));
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let fields: Vec<_> = line.split_whitespace().collect();
+        let result = match fields.as_slice() {
+            [] => continue,
+            [cards] => remove_cards(&mut strategy, cards),
+            [player, dealer] => solve_hand(&mut strategy, player, dealer),
+            _ => Err(anyhow!(
+                "Expected cards leaving the shoe, or a hand and a dealer card"
+            )),
+        };
+        // A mistyped line should not end the session
+        if let Err(error) = result {
+            eprintln!("Error: {error}");
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_cards(cards: &str) -> Result<Vec<Card>> {
+    cards
+        .split(':')
+        .map(|part| Ok(Card::from_rank(Rank::from_value(parse_card_value(part)?)?)))
+        .collect()
+}
+
+fn remove_cards(strategy: &mut StrategyGenerator<CountShoe>, cards: &str) -> Result<()> {
+    for card in parse_cards(cards)? {
+        strategy.remove_card(&card)?;
+    }
+    println!("Cards left: {}", strategy.shoe().total());
+    Ok(())
+}
+
+fn solve_hand(
+    strategy: &mut StrategyGenerator<CountShoe>,
+    player: &str,
+    dealer: &str,
+) -> Result<()> {
+    let player_hand = Hand::from_cards(&parse_cards(player)?);
+    let dealer_upcard = Card::from_rank(Rank::from_value(parse_card_value(dealer)?)?);
+
+    let evs = strategy.eval_round(player_hand, dealer_upcard, player_hand.is_pair());
+    let (action, ev) = evs.best();
+    println!(
+        "{player} vs {dealer}: {} (ev = {ev:.4})",
+        action_to_long_colored_string(&action)
+    );
+    Ok(())
+}
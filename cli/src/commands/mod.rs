@@ -1,2 +1,11 @@
+pub mod compositions;
+pub mod count_metrics;
+pub mod dealer;
+pub mod edge;
 pub mod hand;
+pub mod impact;
+pub mod indices;
+pub mod live;
+pub mod rules;
+pub mod sweep;
 pub mod table;
@@ -0,0 +1,47 @@
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use engine::preset::Preset;
+
+use crate::utils::format::{
+    blackjack_payout_to_string, soft_17_rule_to_string, surrender_to_string,
+};
+
+#[derive(Args)]
+pub struct RulesCommandArgs {
+    #[command(subcommand)]
+    command: RulesCommands,
+}
+
+#[derive(Subcommand)]
+enum RulesCommands {
+    List,
+}
+
+pub fn cmd_rules(args: &RulesCommandArgs) -> Result<()> {
+    match args.command {
+        RulesCommands::List => list_presets(),
+    }
+
+    Ok(())
+}
+
+fn list_presets() {
+    for preset in Preset::all() {
+        let rules = &preset.rules;
+        println!("{}: {}", preset.name, preset.description);
+        println!(
+            "  blackjack payout: {}",
+            blackjack_payout_to_string(&rules.blackjack_payout)
+        );
+        println!("  decks: {}", rules.num_decks);
+        println!(
+            "  dealer soft 17: {}",
+            soft_17_rule_to_string(&rules.dealer_soft_17)
+        );
+        println!("  double after split: {}", rules.double_after_split_allowed);
+        println!("  surrender: {}", surrender_to_string(&rules.surrender));
+        println!("  max splits: {}", rules.max_splits);
+        println!("  dealer peek: {}", rules.dealer_peek);
+        println!();
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow};
+use clap::Args;
+use engine::{
+    options::GeneratorOptions,
+    rules::Rules,
+    shoe::CountShoe,
+    strategy::{DecisionTable, StrategyGenerator, StrategyTable},
+};
+use serde::Serialize;
+
+use crate::{
+    args::{
+        precision::PrecisionArgs,
+        rules::RulesArgs,
+        sweep::{SweepArg, SweepValue, parse_sweep},
+    },
+    commands::table::{PrintFormat, Report, TableType},
+    utils::format::{action_to_string, card_value_to_string},
+};
+
+#[derive(Args)]
+pub struct SweepArgs {
+    #[arg(long, short, default_value_t = PrintFormat::Csv)]
+    format: PrintFormat,
+
+    /// Parameter to sweep, as `name` or `name=values` (e.g. `num-decks=1..8`, `surrender=none,late`)
+    #[arg(long, required = true, value_parser = parse_sweep)]
+    sweep: Vec<SweepArg>,
+
+    /// Also report the chart cells that differ from the base rules
+    #[arg(long)]
+    cells: bool,
+
+    #[command(flatten)]
+    rules: RulesArgs,
+
+    #[command(flatten)]
+    precision: PrecisionArgs,
+
+    /// Threads solving the charts, one per core by default
+    #[arg(long)]
+    threads: Option<usize>,
+}
+
+// Points in the order of the grid, markdown and CSV headers name the swept parameters
+#[derive(Serialize)]
+#[serde(transparent)]
+struct SweepReport {
+    #[serde(skip)]
+    params: Vec<&'static str>,
+    points: Vec<SweepPoint>,
+}
+
+#[derive(Serialize)]
+struct SweepPoint {
+    #[serde(flatten)]
+    params: BTreeMap<String, String>,
+    ev: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changed_cells: Option<Vec<ChangedCell>>,
+}
+
+#[derive(Serialize)]
+struct ChangedCell {
+    table: String,
+    player: String,
+    dealer: String,
+    base: String,
+    action: String,
+}
+
+struct Charts {
+    hard: StrategyTable,
+    soft: StrategyTable,
+    pair: StrategyTable,
+}
+
+pub fn cmd_sweep(args: &SweepArgs) -> Result<()> {
+    if args.sweep.len() > 2 {
+        return Err(anyhow!("At most two parameters can be swept"));
+    }
+    if args.sweep.len() == 2 && args.sweep[0].param == args.sweep[1].param {
+        return Err(anyhow!("The same parameter cannot be swept twice"));
+    }
+
+    let base_rules = Rules::try_from(&args.rules)?;
+    let options = GeneratorOptions::try_from(&args.precision)?;
+    let threads = args.threads.unwrap_or(0);
+    let base_charts = if args.cells {
+        Some(generate_charts(base_rules, options, threads)?.1)
+    } else {
+        None
+    };
+
+    let mut points = Vec::new();
+    for values in grid(&args.sweep) {
+        let mut rules = base_rules;
+        for value in &values {
+            value.apply(&mut rules);
+        }
+        rules.validate()?;
+
+        let params = args
+            .sweep
+            .iter()
+            .zip(&values)
+            .map(|(sweep, value)| (sweep.param.name().to_string(), value.label()))
+            .collect();
+
+        let point = match &base_charts {
+            Some(base_charts) => {
+                let (ev, charts) = generate_charts(rules, options, threads)?;
+                SweepPoint {
+                    params,
+                    ev,
+                    changed_cells: Some(changed_cells(base_charts, &charts)),
+                }
+            }
+            None => {
+                let shoe = CountShoe::new(rules.num_decks)?;
+                SweepPoint {
+                    params,
+                    ev: StrategyGenerator::with_options(rules, shoe, options)?.expected_return(),
+                    changed_cells: None,
+                }
+            }
+        };
+        points.push(point);
+    }
+
+    let report = SweepReport {
+        params: args.sweep.iter().map(|arg| arg.param.name()).collect(),
+        points,
+    };
+    args.format.echo_options(&options);
+    args.format.print_report(&report)
+}
+
+fn grid(sweep: &[SweepArg]) -> Vec<Vec<SweepValue>> {
+    let mut grid = vec![Vec::new()];
+    for arg in sweep {
+        grid = grid
+            .into_iter()
+            .flat_map(|values| {
+                arg.values.iter().map(move |value| {
+                    let mut values = values.clone();
+                    values.push(*value);
+                    values
+                })
+            })
+            .collect();
+    }
+    grid
+}
+
+fn generate_charts(
+    rules: Rules,
+    options: GeneratorOptions,
+    threads: usize,
+) -> Result<(f64, Charts)> {
+    let shoe = CountShoe::new(rules.num_decks)?;
+    let mut generator = StrategyGenerator::with_options(rules, shoe, options)?;
+    let ev = generator.expected_return();
+    let charts = Charts {
+        hard: generator.table_parallel(DecisionTable::Hard, threads)?,
+        soft: generator.table_parallel(DecisionTable::Soft, threads)?,
+        pair: generator.table_parallel(DecisionTable::Pair, threads)?,
+    };
+    Ok((ev, charts))
+}
+
+fn changed_cells(base: &Charts, charts: &Charts) -> Vec<ChangedCell> {
+    let mut cells = Vec::new();
+    for (name, table_type, base_table, table) in [
+        ("hard", TableType::Hard, &base.hard, &charts.hard),
+        ("soft", TableType::Soft, &base.soft, &charts.soft),
+        ("pair", TableType::Pair, &base.pair, &charts.pair),
+    ] {
+        for player_value in table.player_value_range() {
+            for dealer_value in table.dealer_value_range() {
+                let base_action = base_table.get(player_value, dealer_value).action;
+                let action = table.get(player_value, dealer_value).action;
+                if base_action != action {
+                    cells.push(ChangedCell {
+                        table: name.into(),
+                        player: table_type.get_label(player_value),
+                        dealer: card_value_to_string(dealer_value),
+                        base: action_to_string(&base_action),
+                        action: action_to_string(&action),
+                    });
+                }
+            }
+        }
+    }
+    cells
+}
+
+fn format_changed_cells(cells: &[ChangedCell]) -> String {
+    cells
+        .iter()
+        .map(|cell| {
+            format!(
+                "{} {} vs {}: {} -> {}",
+                cell.table, cell.player, cell.dealer, cell.base, cell.action
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl SweepReport {
+    fn has_cells(&self) -> bool {
+        self.points
+            .iter()
+            .any(|point| point.changed_cells.is_some())
+    }
+}
+
+impl Report for SweepReport {
+    fn print_markdown(&self) {
+        let cells = self.has_cells();
+
+        print!("|");
+        for param in &self.params {
+            print!(" {param} |");
+        }
+        print!(" EV |");
+        if cells {
+            print!(" Changed cells |");
+        }
+        println!();
+
+        print!("|");
+        for _ in &self.params {
+            print!("---|");
+        }
+        print!("---:|");
+        if cells {
+            print!("---|");
+        }
+        println!();
+
+        for point in &self.points {
+            print!("|");
+            for param in &self.params {
+                print!(" {} |", point.params[*param]);
+            }
+            print!(" {:+.4}% |", point.ev * 100.0);
+            if let Some(changed_cells) = &point.changed_cells {
+                print!(" {} |", format_changed_cells(changed_cells));
+            }
+            println!();
+        }
+    }
+
+    fn print_csv(&self) {
+        let cells = self.has_cells();
+
+        for param in &self.params {
+            print!("{param},");
+        }
+        print!("ev");
+        if cells {
+            print!(",changed_cells,changes");
+        }
+        println!();
+
+        for point in &self.points {
+            for param in &self.params {
+                print!("{},", point.params[*param]);
+            }
+            print!("{}", point.ev);
+            if let Some(changed_cells) = &point.changed_cells {
+                print!(
+                    ",{},\"{}\"",
+                    changed_cells.len(),
+                    format_changed_cells(changed_cells)
+                );
+            }
+            println!();
+        }
+    }
+}
@@ -1,24 +1,57 @@
-use std::fmt;
+use std::{
+    fmt, fs,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+};
 
+use anyhow::{Context, Result};
 use clap::{Args, ValueEnum};
 use engine::{
+    options::GeneratorOptions,
+    rules::Rules,
+    saved::SavedTables,
     shoe::InfiniteShoe,
-    strategy::{StrategyGenerator, StrategyTable},
+    strategy::{DecisionTable, StrategyGenerator, StrategyTable},
 };
+use indicatif::{ProgressBar, ProgressStyle};
 use serde::Serialize;
+use tables::precomputed_tables;
 
 use crate::{
-    args::rules::RulesArgs,
-    utils::format::{action_to_colored_string, action_to_string},
+    args::{precision::PrecisionArgs, rules::RulesArgs},
+    utils::format::{
+        action_to_colored_string, action_to_string, card_value_to_string, options_to_string,
+    },
 };
 
 #[derive(ValueEnum, Clone)]
-enum PrintFormat {
+pub enum PrintFormat {
     Markdown,
-    CSV,
+    Csv,
     Json,
 }
 
+impl PrintFormat {
+    // Echoes the solver settings so results can be reproduced, machine readable formats only
+    // hold data so they get them on stderr
+    pub fn echo_options(&self, options: &GeneratorOptions) {
+        let line = format!("Solver: {}", options_to_string(options));
+        match self {
+            PrintFormat::Markdown => println!("{line}\n"),
+            PrintFormat::Csv | PrintFormat::Json => eprintln!("{line}"),
+        }
+    }
+
+    pub fn print_report(&self, report: &impl Report) -> Result<()> {
+        match self {
+            PrintFormat::Markdown => report.print_markdown(),
+            PrintFormat::Csv => report.print_csv(),
+            PrintFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Display for PrintFormat {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -26,7 +59,7 @@ impl fmt::Display for PrintFormat {
             "{}",
             match self {
                 PrintFormat::Markdown => "markdown",
-                PrintFormat::CSV => "csv",
+                PrintFormat::Csv => "csv",
                 PrintFormat::Json => "json",
             }
         )
@@ -49,49 +82,144 @@ pub struct TableArgs {
 
     #[command(flatten)]
     rules: RulesArgs,
+
+    #[command(flatten)]
+    precision: PrecisionArgs,
+
+    /// Threads solving the table, one per core by default
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// Solve the tables even when they were precomputed for these rules
+    #[arg(long)]
+    no_precomputed: bool,
+
+    /// Print the tables of a JSON file written by an earlier run instead of solving them, the
+    /// rules and solver flags are then ignored
+    #[arg(long)]
+    load: Option<PathBuf>,
 }
 
-pub fn cmd_table(args: &TableArgs) {
-    let all = !args.hard && !args.soft && !args.pair;
-    let shoe = InfiniteShoe::new();
-    let mut generator = StrategyGenerator::new((&args.rules).into(), shoe);
+pub fn cmd_table(args: &TableArgs) -> Result<()> {
+    let saved = match &args.load {
+        Some(path) => load_tables(args, path)?,
+        None => solve_tables(args)?,
+    };
+    args.format.echo_options(&saved.options);
+
     let printer: Box<dyn TablePrinter> = match args.format {
         PrintFormat::Markdown => Box::new(MarkdownTablePrinter {}),
-        PrintFormat::CSV => Box::new(CSVTablePrinter {}),
-        PrintFormat::Json => Box::new(JsonTablePrinter {}),
+        PrintFormat::Csv => Box::new(CSVTablePrinter {}),
+        // The saved document holds every table and can be loaded back with --load
+        PrintFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&saved)?);
+            return Ok(());
+        }
     };
 
-    if args.hard || all {
-        println!("\n\n# HARD \n");
-        let table = generator.hard_table();
-        printer.print(&table, TableType::Hard);
+    for (table, table_type, title) in [
+        (DecisionTable::Hard, TableType::Hard, "HARD "),
+        (DecisionTable::Soft, TableType::Soft, "SOFT"),
+        (DecisionTable::Pair, TableType::Pair, "PAIR"),
+    ] {
+        if let Some(value) = saved.get(table) {
+            println!("\n\n# {title}\n");
+            printer.print(value, table_type);
+        }
     }
 
-    if args.soft || all {
-        println!("\n\n# SOFT\n");
-        let table = generator.soft_table();
-        printer.print(&table, TableType::Soft);
+    Ok(())
+}
+
+fn is_requested(args: &TableArgs, table: DecisionTable) -> bool {
+    let all = !args.hard && !args.soft && !args.pair;
+    all || match table {
+        DecisionTable::Hard => args.hard,
+        DecisionTable::Soft => args.soft,
+        DecisionTable::Pair => args.pair,
     }
+}
 
-    if args.pair || all {
-        println!("\n\n# PAIR\n");
-        let table = generator.pair_table();
-        printer.print(&table, TableType::Pair);
+fn solve_tables(args: &TableArgs) -> Result<SavedTables> {
+    let shoe = InfiniteShoe::new();
+    let options = GeneratorOptions::try_from(&args.precision)?;
+    let mut generator =
+        StrategyGenerator::with_options(Rules::try_from(&args.rules)?, shoe, options)?;
+    let threads = args.threads.unwrap_or(0);
+
+    let requested: Vec<_> = DecisionTable::ALL
+        .into_iter()
+        .filter(|&table| is_requested(args, table))
+        .collect();
+    let precomputed = if args.no_precomputed {
+        None
+    } else {
+        let precomputed = precomputed_tables();
+        requested
+            .iter()
+            .map(|&table| precomputed.get(generator.rules(), &options, table).cloned())
+            .collect()
+    };
+
+    let tables = match precomputed {
+        Some(tables) => tables,
+        None => {
+            // The bar is drawn on stderr and cleared before the tables are printed
+            let bar = ProgressBar::new(0).with_style(
+                ProgressStyle::with_template("Solving cells {bar:40} {pos}/{len} ({eta} left)")
+                    .expect("the progress template is valid"),
+            );
+            let tables = generator.tables_parallel(&requested, threads, |progress| {
+                bar.set_length(progress.total as u64);
+                bar.set_position(progress.done as u64);
+                ControlFlow::Continue(())
+            })?;
+            bar.finish_and_clear();
+            tables
+        }
+    };
+
+    let mut saved = SavedTables::new(*generator.rules(), options, true);
+    for (table, value) in requested.into_iter().zip(tables) {
+        saved.set(table, value);
     }
+    Ok(saved)
+}
+
+fn load_tables(args: &TableArgs, path: &Path) -> Result<SavedTables> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+    let mut saved: SavedTables = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid saved tables {}", path.display()))?;
+    saved.validate()?;
+
+    for table in DecisionTable::ALL {
+        if !is_requested(args, table) {
+            saved.take(table);
+        }
+    }
+    Ok(saved)
 }
 
 trait TablePrinter {
-    fn print(&self, table: &StrategyTable, table_type: TableType) -> ();
+    fn print(&self, table: &StrategyTable, table_type: TableType);
+}
+
+// Rows reported by the other commands, JSON output serializes the whole report
+pub trait Report: Serialize {
+    fn print_markdown(&self);
+
+    fn print_csv(&self);
 }
 
-enum TableType {
+pub enum TableType {
     Hard,
     Soft,
     Pair,
 }
 
 impl TableType {
-    fn get_label(&self, value: u8) -> String {
+    pub fn get_label(&self, value: u8) -> String {
         match self {
             TableType::Hard => value.to_string(),
             TableType::Soft => {
@@ -99,10 +227,7 @@ impl TableType {
                 format!("A{hard_part}")
             }
             TableType::Pair => {
-                let value = match value {
-                    11 => "A".into(),
-                    _ => value.to_string(),
-                };
+                let value = card_value_to_string(value);
                 format!("{value},{value}")
             }
         }
@@ -115,10 +240,7 @@ impl TablePrinter for MarkdownTablePrinter {
     fn print(&self, table: &StrategyTable, table_type: TableType) {
         print!("| Hand \\ Dealer |");
         for dealer_value in table.dealer_value_range() {
-            let header = match dealer_value {
-                11 => "A".to_string(),
-                _ => dealer_value.to_string(),
-            };
+            let header = card_value_to_string(dealer_value);
             print!("  {header:<2} |");
         }
         println!();
@@ -139,19 +261,22 @@ impl TablePrinter for MarkdownTablePrinter {
             }
             println!();
         }
+
+        let error = table
+            .values
+            .iter()
+            .map(|value| value.error)
+            .fold(0.0, f64::max);
+        println!("\nPruning error bound: ±{error:.2e}");
     }
 }
 
 struct CSVTablePrinter;
 
 impl TablePrinter for CSVTablePrinter {
-    fn print(&self, table: &StrategyTable, table_type: TableType) -> () {
+    fn print(&self, table: &StrategyTable, table_type: TableType) {
         for dealer_value in table.dealer_value_range() {
-            let header = match dealer_value {
-                11 => "A".to_string(),
-                _ => dealer_value.to_string(),
-            };
-            print!(",{}", header);
+            print!(",{}", card_value_to_string(dealer_value));
         }
         println!();
 
@@ -167,49 +292,3 @@ impl TablePrinter for CSVTablePrinter {
         }
     }
 }
-
-#[derive(Serialize)]
-struct PlayerRow {
-    player: String,
-    entries: Vec<DealerEntry>,
-}
-
-#[derive(Serialize)]
-struct DealerEntry {
-    dealer: String,
-    action: String,
-    ev: f64,
-}
-
-struct JsonTablePrinter;
-
-impl TablePrinter for JsonTablePrinter {
-    fn print(&self, table: &StrategyTable, table_type: TableType) -> () {
-        let mut rows = Vec::new();
-
-        for player_value in table.player_value_range() {
-            let mut row = PlayerRow {
-                player: table_type.get_label(player_value),
-                entries: Vec::new(),
-            };
-
-            for dealer_value in table.dealer_value_range() {
-                let entry = table.get(player_value, dealer_value);
-                let dealer_label = match dealer_value {
-                    11 => "A".to_string(),
-                    _ => dealer_value.to_string(),
-                };
-                row.entries.push(DealerEntry {
-                    dealer: dealer_label,
-                    action: action_to_string(&entry.action),
-                    ev: entry.ev,
-                });
-            }
-
-            rows.push(row);
-        }
-
-        let json = serde_json::to_string_pretty(&rows).unwrap();
-        println!("{}", json);
-    }
-}
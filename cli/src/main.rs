@@ -1,7 +1,18 @@
-use clap::{Parser, Subcommand, command};
+use std::process;
+
+use clap::{Parser, Subcommand};
 
 use crate::commands::{
+    compositions::{CompositionsArgs, cmd_compositions},
+    count_metrics::{CountMetricsArgs, cmd_count_metrics},
+    dealer::{DealerArgs, cmd_dealer},
+    edge::{EdgeArgs, cmd_edge},
     hand::{HandArgs, cmd_hand},
+    impact::{ImpactArgs, cmd_impact},
+    indices::{IndicesArgs, cmd_indices},
+    live::{LiveArgs, cmd_live},
+    rules::{RulesCommandArgs, cmd_rules},
+    sweep::{SweepArgs, cmd_sweep},
     table::{TableArgs, cmd_table},
 };
 
@@ -18,21 +29,38 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
+    Compositions(CompositionsArgs),
+    CountMetrics(CountMetricsArgs),
+    Dealer(DealerArgs),
+    Edge(EdgeArgs),
     Hand(HandArgs),
+    Impact(ImpactArgs),
+    Indices(Box<IndicesArgs>),
+    Live(LiveArgs),
+    Rules(RulesCommandArgs),
+    Sweep(SweepArgs),
     Table(TableArgs),
 }
 
 pub fn main() {
     let cli = Cli::parse();
 
-    match &cli.command {
-        Commands::Hand(args) => {
-            if let Err(error) = cmd_hand(args) {
-                eprintln!("Error: {error}")
-            }
-        }
-        Commands::Table(args) => {
-            cmd_table(args);
-        }
+    let result = match &cli.command {
+        Commands::Compositions(args) => cmd_compositions(args),
+        Commands::CountMetrics(args) => cmd_count_metrics(args),
+        Commands::Dealer(args) => cmd_dealer(args),
+        Commands::Edge(args) => cmd_edge(args),
+        Commands::Hand(args) => cmd_hand(args),
+        Commands::Impact(args) => cmd_impact(args),
+        Commands::Indices(args) => cmd_indices(args),
+        Commands::Live(args) => cmd_live(args),
+        Commands::Rules(args) => cmd_rules(args),
+        Commands::Sweep(args) => cmd_sweep(args),
+        Commands::Table(args) => cmd_table(args),
+    };
+
+    if let Err(error) = result {
+        eprintln!("Error: {error}");
+        process::exit(1);
     }
 }
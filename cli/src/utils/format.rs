@@ -1,5 +1,11 @@
 use colored::{ColoredString, Colorize};
-use engine::strategy::PlayerAction;
+use engine::{
+    cache::CacheStats,
+    hand::Hand,
+    options::{GeneratorOptions, PruningStrategy, Solver, SplitMode},
+    rules::{BlackjackPayout, Soft17Rule, SurrenderType},
+    strategy::PlayerAction,
+};
 
 pub fn action_to_string(action: &PlayerAction) -> String {
     String::from(match action {
@@ -44,3 +50,75 @@ pub fn action_to_long_colored_string(action: &PlayerAction) -> ColoredString {
     let s = action_to_long_string(action);
     colored_string(s, action)
 }
+
+pub fn blackjack_payout_to_string(payout: &BlackjackPayout) -> String {
+    String::from(match payout {
+        BlackjackPayout::Ratio3to2 => "3to2",
+        BlackjackPayout::Ratio6to5 => "6to5",
+    })
+}
+
+pub fn soft_17_rule_to_string(rule: &Soft17Rule) -> String {
+    String::from(match rule {
+        Soft17Rule::Hit => "hit",
+        Soft17Rule::Stand => "stand",
+    })
+}
+
+pub fn surrender_to_string(surrender: &SurrenderType) -> String {
+    String::from(match surrender {
+        SurrenderType::None => "none",
+        SurrenderType::Early => "early",
+        SurrenderType::Late => "late",
+    })
+}
+
+pub fn options_to_string(options: &GeneratorOptions) -> String {
+    let pruning = match options.pruning {
+        PruningStrategy::Branch => "branch",
+        PruningStrategy::Draw => "draw",
+        PruningStrategy::None => "none",
+    };
+    let max_depth = match options.max_depth {
+        Some(depth) => depth.to_string(),
+        None => String::from("none"),
+    };
+    let split = match options.split {
+        SplitMode::Independent => "independent",
+        SplitMode::Sequential => "sequential",
+    };
+    let solver = match options.solver {
+        Solver::Recursive => "recursive",
+        Solver::Iterative => "iterative",
+    };
+    format!(
+        "precision {:e}, pruning {pruning}, max depth {max_depth}, split mode {split}, \
+         solver {solver}",
+        options.epsilon
+    )
+}
+
+// Card and dealer values as shown in charts, with 11 for an ace
+pub fn card_value_to_string(value: u8) -> String {
+    match value {
+        11 => String::from("A"),
+        _ => value.to_string(),
+    }
+}
+
+pub fn hand_to_string(hand: &Hand) -> String {
+    hand.cards()
+        .map(|card| card_value_to_string(card.rank.value()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn cache_stats_to_string(stats: &CacheStats) -> String {
+    format!(
+        "Cache: {} entries ({:.1} MB), {} hits, {} misses",
+        stats.entries,
+        stats.bytes as f64 / (1 << 20) as f64,
+        stats.hits,
+        stats.misses
+    )
+}
@@ -0,0 +1,15 @@
+use anyhow::{Result, anyhow};
+use regex::Regex;
+
+pub fn parse_card_value(value_string: &str) -> Result<u8> {
+    let re = Regex::new("[2-9JQKA]|10").unwrap();
+    if !re.is_match(value_string) {
+        Err(anyhow!("Invalid card value {}", value_string))
+    } else {
+        Ok(match value_string {
+            "A" => 11,
+            "J" | "Q" | "K" | "10" => 10,
+            _ => value_string.parse()?,
+        })
+    }
+}
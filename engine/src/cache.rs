@@ -0,0 +1,382 @@
+use std::{
+    hash::Hash,
+    io::{self, Read, Write},
+    mem::{self, size_of},
+    ops::AddAssign,
+};
+
+use ahash::AHashMap;
+
+use crate::{
+    distribution::OutcomesByDealer,
+    hand::Hand,
+    options::{GeneratorOptions, PruningStrategy, SplitMode},
+    rules::{BlackjackPayout, Rules, Soft17Rule, SurrenderType},
+};
+
+// Summed over every cache of a generator, bytes estimate the memory held by the entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify), tsify(into_wasm_abi))]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+impl AddAssign for CacheStats {
+    fn add_assign(&mut self, other: Self) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+        self.entries += other.entries;
+        self.bytes += other.bytes;
+    }
+}
+
+// Memory a cached value holds outside of the map
+pub(crate) trait HeapSize {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl HeapSize for Vec<(Hand, f64)> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<(Hand, f64)>()
+    }
+}
+
+impl HeapSize for OutcomesByDealer {
+    fn heap_size(&self) -> usize {
+        self.len() * size_of::<((u8, bool), i32, f64)>()
+    }
+}
+
+// Map split in the entries used since it was last aged and the older ones, which are dropped
+// when it is aged again. Aging it whenever the recent entries reach a limit keeps its memory
+// bounded while the entries still in use survive
+pub(crate) struct Cache<K, V> {
+    recent: AHashMap<K, V>,
+    older: AHashMap<K, V>,
+    recent_bytes: usize,
+    older_bytes: usize,
+    // Bytes the recent entries grew by since `insert` last reported them
+    grown_bytes: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl<K: Copy + Eq + Hash, V: HeapSize> Cache<K, V> {
+    pub(crate) fn new() -> Self {
+        Self {
+            recent: AHashMap::new(),
+            older: AHashMap::new(),
+            recent_bytes: 0,
+            older_bytes: 0,
+            grown_bytes: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn entry_size(value: &V) -> usize {
+        size_of::<(K, V)>() + value.heap_size()
+    }
+
+    pub(crate) fn get(&mut self, key: &K) -> Option<&V> {
+        // Older entries are moved back with the recent ones once used
+        if !self.older.is_empty()
+            && !self.recent.contains_key(key)
+            && let Some(value) = self.older.remove(key)
+        {
+            let size = Self::entry_size(&value);
+            self.older_bytes -= size;
+            self.recent_bytes += size;
+            self.grown_bytes += size;
+            self.recent.insert(*key, value);
+        }
+
+        let value = self.recent.get(key);
+        match value {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        value
+    }
+
+    // Returns how many bytes the recent entries grew by since the previous insert, including
+    // older entries used again
+    pub(crate) fn insert(&mut self, key: K, value: V) -> usize {
+        let size = Self::entry_size(&value);
+        self.recent_bytes += size;
+        self.grown_bytes += size;
+        if let Some(previous) = self.recent.insert(key, value) {
+            let size = Self::entry_size(&previous);
+            self.recent_bytes -= size;
+            self.grown_bytes = self.grown_bytes.saturating_sub(size);
+        }
+        mem::take(&mut self.grown_bytes)
+    }
+
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&K) -> bool) {
+        for (map, bytes) in [
+            (&mut self.recent, &mut self.recent_bytes),
+            (&mut self.older, &mut self.older_bytes),
+        ] {
+            map.retain(|key, value| {
+                let kept = keep(key);
+                if !kept {
+                    *bytes -= Self::entry_size(value);
+                }
+                kept
+            });
+        }
+    }
+
+    pub(crate) fn recent_bytes(&self) -> usize {
+        self.recent_bytes
+    }
+
+    pub(crate) fn age(&mut self) {
+        self.older = mem::take(&mut self.recent);
+        self.older_bytes = mem::take(&mut self.recent_bytes);
+        self.grown_bytes = 0;
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.older.len() + self.recent.len(),
+            bytes: self.older_bytes + self.recent_bytes,
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.older.iter().chain(self.recent.iter())
+    }
+
+    #[cfg(feature = "parallel")]
+    pub(crate) fn drain(&mut self) -> impl Iterator<Item = (K, V)> {
+        self.older_bytes = 0;
+        self.recent_bytes = 0;
+        self.grown_bytes = 0;
+        self.older.drain().chain(self.recent.drain())
+    }
+
+    // Takes the entries and statistics of a cache whose keys are disjoint from these
+    #[cfg(feature = "parallel")]
+    pub(crate) fn absorb(&mut self, other: Self) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+        self.recent_bytes += other.older_bytes + other.recent_bytes;
+        self.recent.extend(other.older);
+        self.recent.extend(other.recent);
+    }
+}
+
+// Saved caches are little endian binary, they only need to be read back by this crate
+
+pub(crate) fn write_u64(writer: &mut impl Write, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+pub(crate) fn write_f64(writer: &mut impl Write, value: f64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+pub(crate) fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+pub(crate) fn read_f64(reader: &mut impl Read) -> io::Result<f64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// FNV-1a, unlike the hashers of the caches it does not change between builds
+pub(crate) fn fingerprint(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+// Fingerprint of the settings solved values depend on, encoded field by field so it only
+// changes along with them. Both solver modes reach the same values, so it is left out
+pub(crate) fn settings_fingerprint(rules: &Rules, options: &GeneratorOptions, shoe: u64) -> u64 {
+    let Rules {
+        blackjack_payout,
+        num_decks,
+        dealer_soft_17,
+        double_after_split_allowed,
+        surrender,
+        max_splits,
+        dealer_peek,
+    } = *rules;
+    let GeneratorOptions {
+        epsilon,
+        pruning,
+        max_depth,
+        split,
+        solver: _,
+    } = *options;
+
+    let mut bytes = vec![
+        match blackjack_payout {
+            BlackjackPayout::Ratio3to2 => 0,
+            BlackjackPayout::Ratio6to5 => 1,
+        },
+        num_decks,
+        match dealer_soft_17 {
+            Soft17Rule::Hit => 0,
+            Soft17Rule::Stand => 1,
+        },
+        u8::from(double_after_split_allowed),
+        match surrender {
+            SurrenderType::None => 0,
+            SurrenderType::Early => 1,
+            SurrenderType::Late => 2,
+        },
+        max_splits,
+        u8::from(dealer_peek),
+        match pruning {
+            PruningStrategy::Branch => 0,
+            PruningStrategy::Draw => 1,
+            PruningStrategy::None => 2,
+        },
+        // Depths below three are invalid, so zero stands for no maximum
+        max_depth.unwrap_or(0),
+        match split {
+            SplitMode::Independent => 0,
+            SplitMode::Sequential => 1,
+        },
+    ];
+    bytes.extend(epsilon.to_le_bytes());
+    bytes.extend(shoe.to_le_bytes());
+    fingerprint(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::Solver;
+
+    #[derive(Debug, PartialEq)]
+    struct Value(u32);
+
+    impl HeapSize for Value {}
+
+    const ENTRY: usize = size_of::<(u8, Value)>();
+
+    #[test]
+    fn aging_drops_entries_not_used_since() {
+        let mut cache = Cache::new();
+        cache.insert(1u8, Value(1));
+        cache.insert(2, Value(2));
+        assert_eq!(cache.recent_bytes(), 2 * ENTRY);
+
+        cache.age();
+        assert_eq!(cache.recent_bytes(), 0);
+        // Used entries move back with the recent ones and survive the next aging
+        assert_eq!(cache.get(&1), Some(&Value(1)));
+        cache.age();
+        assert_eq!(cache.get(&1), Some(&Value(1)));
+        assert_eq!(cache.get(&2), None);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+        assert_eq!((stats.entries, stats.bytes), (1, ENTRY));
+    }
+
+    #[test]
+    fn tracks_bytes_of_replaced_and_removed_entries() {
+        let mut cache = Cache::new();
+        cache.insert(1u8, Value(1));
+        cache.insert(1, Value(2));
+        assert_eq!(cache.stats().entries, 1);
+        assert_eq!(cache.recent_bytes(), ENTRY);
+
+        cache.insert(2, Value(3));
+        cache.age();
+        cache.insert(3, Value(4));
+        cache.retain(|key| *key != 2);
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.bytes), (2, 2 * ENTRY));
+        assert_eq!(cache.get(&2), None);
+    }
+
+    #[test]
+    fn inserts_report_how_much_the_recent_entries_grew() {
+        let mut cache = Cache::new();
+        assert_eq!(cache.insert(1u8, Value(1)), ENTRY);
+        assert_eq!(cache.insert(1, Value(2)), 0);
+        cache.insert(2, Value(3));
+
+        // An older entry used again is reported along with the next insert
+        cache.age();
+        assert_eq!(cache.get(&1), Some(&Value(2)));
+        assert_eq!(cache.insert(3, Value(4)), 2 * ENTRY);
+    }
+
+    #[test]
+    fn binary_values_round_trip() {
+        let mut bytes = Vec::new();
+        write_u64(&mut bytes, u64::MAX - 1).unwrap();
+        write_f64(&mut bytes, -0.125).unwrap();
+        assert_eq!(bytes.len(), 16);
+
+        let reader = &mut &bytes[..];
+        assert_eq!(read_u64(reader).unwrap(), u64::MAX - 1);
+        assert_eq!(read_f64(reader).unwrap(), -0.125);
+        assert!(read_u64(reader).is_err());
+    }
+
+    #[test]
+    fn fingerprint_is_fnv_1a() {
+        assert_eq!(fingerprint(b""), 0xcbf29ce484222325);
+        assert_eq!(fingerprint(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fingerprint(b"foobar"), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn settings_fingerprint_ignores_only_the_solver() {
+        let rules = Rules::default();
+        let options = GeneratorOptions::default();
+        let base = settings_fingerprint(&rules, &options, 0);
+
+        let iterative = GeneratorOptions {
+            solver: Solver::Iterative,
+            ..options
+        };
+        let recursive = GeneratorOptions {
+            solver: Solver::Recursive,
+            ..options
+        };
+        assert_eq!(settings_fingerprint(&rules, &iterative, 0), base);
+        assert_eq!(settings_fingerprint(&rules, &recursive, 0), base);
+
+        let other_rules = Rules {
+            dealer_peek: !rules.dealer_peek,
+            ..rules
+        };
+        let other_options = GeneratorOptions {
+            max_depth: Some(3),
+            ..options
+        };
+        assert_ne!(settings_fingerprint(&other_rules, &options, 0), base);
+        assert_ne!(settings_fingerprint(&rules, &other_options, 0), base);
+        assert_ne!(settings_fingerprint(&rules, &options, 1), base);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::error::Error;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Rank {
     Two,
@@ -31,8 +33,8 @@ impl Rank {
         }
     }
 
-    pub fn from_value(value: u8) -> Self {
-        match value {
+    pub fn from_value(value: u8) -> Result<Self, Error> {
+        Ok(match value {
             1 => Rank::Ace,
             2 => Rank::Two,
             3 => Rank::Three,
@@ -44,8 +46,27 @@ impl Rank {
             9 => Rank::Nine,
             10 => Rank::Ten,
             11 => Rank::Ace,
-            _ => unreachable!(),
-        }
+            _ => return Err(Error::InvalidCardValue(value)),
+        })
+    }
+
+    pub fn index(&self) -> usize {
+        (self.value() - 2) as usize
+    }
+
+    pub(crate) fn from_index(index: usize) -> Self {
+        [
+            Rank::Two,
+            Rank::Three,
+            Rank::Four,
+            Rank::Five,
+            Rank::Six,
+            Rank::Seven,
+            Rank::Eight,
+            Rank::Nine,
+            Rank::Ten,
+            Rank::Ace,
+        ][index]
     }
 
     pub fn all() -> impl Iterator<Item = Rank> {
@@ -78,3 +99,23 @@ impl Card {
         Self { rank }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_values_to_ranks() {
+        assert_eq!(Rank::from_value(1), Ok(Rank::Ace));
+        assert_eq!(Rank::from_value(11), Ok(Rank::Ace));
+        for rank in Rank::all() {
+            assert_eq!(
+                Rank::from_value(rank.value()).unwrap().value(),
+                rank.value()
+            );
+        }
+        for value in [0, 12, u8::MAX] {
+            assert_eq!(Rank::from_value(value), Err(Error::InvalidCardValue(value)));
+        }
+    }
+}
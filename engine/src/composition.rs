@@ -0,0 +1,171 @@
+use std::hash::Hash;
+
+use crate::{
+    card::Card,
+    error::Error,
+    hand::Hand,
+    shoe::{HandType, Shoe, iter_compositions},
+    strategy::{RoundEvs, StrategyGenerator, StrategyValue},
+};
+
+pub struct CompositionValue {
+    pub hand: Hand,
+    pub weight: f64,
+    pub value: StrategyValue,
+    // Whether the best play differs from the one for the total as a whole
+    pub flipped: bool,
+}
+
+pub struct CompositionAnalysis {
+    pub player_value: u8,
+    pub hand_type: HandType,
+    pub dealer_upcard: Card,
+    pub total: StrategyValue,
+    pub compositions: Vec<CompositionValue>,
+}
+
+impl<S: Shoe + Clone + Eq + Hash> StrategyGenerator<S> {
+    pub fn analyze_compositions(
+        &mut self,
+        player_value: u8,
+        hand_type: HandType,
+        dealer_upcard: Card,
+        max_cards: u8,
+    ) -> Result<CompositionAnalysis, Error> {
+        let valid_values = match hand_type {
+            HandType::Hard => 4..=21,
+            HandType::Soft => 12..=21,
+        };
+        if !valid_values.contains(&player_value) {
+            return Err(Error::InvalidPlayerTotal(player_value, hand_type));
+        }
+
+        let mut shoe = self.shoe().clone();
+        shoe.remove_card(&dealer_upcard);
+
+        // The decision for the total is the one a strategy table shows
+        let mut evs = Vec::new();
+        let mut error = 0.0;
+        let mut total_weight = 0.0;
+        for (hand, weight) in shoe.iter_player_hands(player_value, hand_type) {
+            let (hand_evs, hand_error) = self.eval_round_with_error(hand, dealer_upcard, false);
+            evs.push((hand_evs, weight));
+            error += hand_error * weight;
+            total_weight += weight;
+        }
+        let total = StrategyValue::from_evs(RoundEvs::weighted_average(&evs), error / total_weight);
+        let (total_action_after_hit, _) = total.evs.best_after_hit();
+
+        let mut compositions: Vec<_> =
+            iter_compositions(&shoe, player_value, hand_type, 2..=max_cards)
+                .map(|(hand, weight)| {
+                    let (evs, error) = self.eval_round_with_error(hand, dealer_upcard, false);
+                    let (value, total_action) = if hand.size() > 2 {
+                        let (action, ev) = evs.best_after_hit();
+                        let value = StrategyValue {
+                            ev,
+                            evs,
+                            action,
+                            error,
+                        };
+                        (value, total_action_after_hit)
+                    } else {
+                        (StrategyValue::from_evs(evs, error), total.action)
+                    };
+
+                    CompositionValue {
+                        hand,
+                        weight,
+                        flipped: value.action != total_action,
+                        value,
+                    }
+                })
+                .collect();
+        compositions.sort_by_key(|composition| composition.hand.size());
+
+        Ok(CompositionAnalysis {
+            player_value,
+            hand_type,
+            dealer_upcard,
+            total,
+            compositions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        card::Rank,
+        options::GeneratorOptions,
+        rules::Rules,
+        shoe::{CountShoe, InfiniteShoe},
+        strategy::PlayerAction,
+    };
+
+    fn card(value: u8) -> Card {
+        Card::from_rank(Rank::from_value(value).unwrap())
+    }
+
+    fn options() -> GeneratorOptions {
+        GeneratorOptions {
+            epsilon: 1e-3,
+            ..GeneratorOptions::default()
+        }
+    }
+
+    #[test]
+    fn ten_two_hits_against_a_four_from_one_deck() {
+        let rules = Rules {
+            num_decks: 1,
+            ..Rules::default()
+        };
+        let shoe = CountShoe::new(1).unwrap();
+        let mut generator = StrategyGenerator::with_options(rules, shoe, options()).unwrap();
+        let analysis = generator
+            .analyze_compositions(12, HandType::Hard, card(4), 2)
+            .unwrap();
+        assert_eq!(analysis.total.action, PlayerAction::Stand);
+        assert_eq!(analysis.compositions.len(), 5);
+
+        // The ten taken out of the shoe makes the dealer bust less and hitting the twelve safer
+        for composition in &analysis.compositions {
+            let ten_two = composition.hand.count(Rank::Ten) == 1;
+            assert_eq!(composition.flipped, ten_two);
+            let expected = if ten_two {
+                PlayerAction::Hit
+            } else {
+                PlayerAction::Stand
+            };
+            assert_eq!(composition.value.action, expected);
+        }
+    }
+
+    #[test]
+    fn compositions_play_the_total_from_an_infinite_shoe() {
+        let shoe = InfiniteShoe::new();
+        let mut generator =
+            StrategyGenerator::with_options(Rules::default(), shoe, options()).unwrap();
+        for (player_value, hand_type, upcard) in [
+            (16, HandType::Hard, 10),
+            (12, HandType::Hard, 4),
+            (18, HandType::Soft, 9),
+        ] {
+            let analysis = generator
+                .analyze_compositions(player_value, hand_type, card(upcard), 4)
+                .unwrap();
+            let total = analysis.total.evs;
+            assert!(analysis.compositions.len() > 1);
+            for composition in &analysis.compositions {
+                let evs = composition.value.evs;
+                assert!(!composition.flipped);
+                assert!((evs.hit - total.hit).abs() < 1e-12);
+                assert!((evs.stand - total.stand).abs() < 1e-12);
+                if composition.hand.size() == 2 {
+                    assert!((evs.double - total.double).abs() < 1e-12);
+                }
+            }
+        }
+    }
+}
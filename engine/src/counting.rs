@@ -0,0 +1,350 @@
+use crate::{
+    card::{Card, Rank},
+    error::Error,
+    removal::RemovalEffects,
+    shoe::{CountShoe, Shoe},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CountingSystem {
+    pub name: &'static str,
+    pub description: &'static str,
+    // Tags indexed like ranks, from two to ace
+    pub tags: [f64; 10],
+}
+
+const SYSTEMS: [CountingSystem; 8] = [
+    CountingSystem {
+        name: "hi-lo",
+        description: "Hi-Lo",
+        tags: [1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, -1.0, -1.0],
+    },
+    CountingSystem {
+        name: "ko",
+        description: "Knock-Out",
+        tags: [1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, -1.0, -1.0],
+    },
+    CountingSystem {
+        name: "hi-opt-1",
+        description: "Hi-Opt I",
+        tags: [0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, -1.0, 0.0],
+    },
+    CountingSystem {
+        name: "hi-opt-2",
+        description: "Hi-Opt II",
+        tags: [1.0, 1.0, 2.0, 2.0, 1.0, 1.0, 0.0, 0.0, -2.0, 0.0],
+    },
+    CountingSystem {
+        name: "omega-2",
+        description: "Omega II",
+        tags: [1.0, 1.0, 2.0, 2.0, 2.0, 1.0, 0.0, -1.0, -2.0, 0.0],
+    },
+    CountingSystem {
+        name: "zen",
+        description: "Zen Count",
+        tags: [1.0, 1.0, 2.0, 2.0, 2.0, 1.0, 0.0, 0.0, -2.0, -1.0],
+    },
+    CountingSystem {
+        name: "wong-halves",
+        description: "Wong Halves",
+        tags: [0.5, 1.0, 1.0, 1.5, 1.0, 0.5, 0.0, -0.5, -1.0, -1.0],
+    },
+    // Only red sevens count, which averages to half a point per seven since suits aren't
+    // tracked
+    CountingSystem {
+        name: "red-7",
+        description: "Red Seven",
+        tags: [1.0, 1.0, 1.0, 1.0, 1.0, 0.5, 0.0, 0.0, -1.0, -1.0],
+    },
+];
+
+// Effect of removing each rank on the insurance bet, up to a scale
+const INSURANCE_EFFECTS: [f64; 10] = [4.0, 4.0, 4.0, 4.0, 4.0, 4.0, 4.0, 4.0, -9.0, 4.0];
+
+// Share of each rank in a deck, used to weight correlations
+const RANK_WEIGHTS: [f64; 10] = [4.0, 4.0, 4.0, 4.0, 4.0, 4.0, 4.0, 4.0, 16.0, 4.0];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SystemMetrics {
+    pub betting_correlation: f64,
+    pub playing_efficiency: f64,
+    pub insurance_correlation: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeckEstimation {
+    FullDeck,
+    HalfDeck,
+    Exact,
+}
+
+impl DeckEstimation {
+    pub fn remaining_decks(&self, shoe: &CountShoe) -> f64 {
+        let decks = shoe.total() as f64 / 52.0;
+        match self {
+            DeckEstimation::FullDeck => decks.round().max(1.0),
+            DeckEstimation::HalfDeck => ((decks * 2.0).round() / 2.0).max(0.5),
+            DeckEstimation::Exact => decks,
+        }
+    }
+}
+
+impl CountingSystem {
+    pub fn all() -> impl Iterator<Item = &'static CountingSystem> {
+        SYSTEMS.iter()
+    }
+
+    pub fn find(name: &str) -> Option<&'static CountingSystem> {
+        SYSTEMS.iter().find(|system| system.name == name)
+    }
+
+    pub fn custom(tags: [f64; 10]) -> Self {
+        Self {
+            name: "custom",
+            description: "Custom tags",
+            tags,
+        }
+    }
+
+    pub fn tag(&self, rank: Rank) -> f64 {
+        self.tags[rank.index()]
+    }
+
+    // Sum of the tags over a single deck
+    pub fn deck_sum(&self) -> f64 {
+        (0..10)
+            .map(Rank::from_index)
+            .map(|rank| {
+                let cards = if rank == Rank::Ten { 16.0 } else { 4.0 };
+                self.tag(rank) * cards
+            })
+            .sum()
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.deck_sum().abs() < 1e-9
+    }
+
+    // Running count of the cards dealt so far, starting from zero
+    pub fn running_count(&self, shoe: &CountShoe) -> f64 {
+        let full_shoe = CountShoe::new(shoe.decks()).expect("shoes have a valid deck count");
+        (0..10)
+            .map(Rank::from_index)
+            .map(|rank| {
+                let dealt = full_shoe.count(rank) - shoe.count(rank);
+                self.tag(rank) * dealt as f64
+            })
+            .sum()
+    }
+
+    // Running count expected once the cards dealt so far came out in the proportions of a full
+    // shoe, always zero for balanced systems. Unbalanced systems gain their deck sum with every
+    // deck dealt, which players usually offset with an initial running count and a pivot
+    pub fn neutral_running_count(&self, shoe: &CountShoe) -> f64 {
+        self.deck_sum() * shoe.dealt() as f64 / 52.0
+    }
+
+    // Running count per remaining deck. Unbalanced systems are converted to a balanced
+    // equivalent by measuring the running count from the neutral one, so their true counts and
+    // indices compare to balanced systems rather than to the running counts players keep
+    pub fn true_count(&self, shoe: &CountShoe, estimation: DeckEstimation) -> f64 {
+        (self.running_count(shoe) - self.neutral_running_count(shoe))
+            / estimation.remaining_decks(shoe)
+    }
+
+    // Starts from a neutral shoe dealt halfway through, then swaps dealt cards tagged against
+    // the count for remaining cards tagged with it until the exact true count reaches the
+    // target
+    pub fn shoe_at_true_count(&self, decks: u8, true_count: i8) -> Result<CountShoe, Error> {
+        let full_shoe = CountShoe::new(decks)?;
+        let mut shoe = full_shoe.clone();
+        for rank in (0..10).map(Rank::from_index) {
+            for _ in 0..full_shoe.count(rank) / 2 {
+                shoe.remove_card(&Card::from_rank(rank));
+            }
+        }
+
+        let target = true_count as f64;
+        let direction = target.signum();
+        while (self.true_count(&shoe, DeckEstimation::Exact) - target) * direction < 0.0 {
+            let remaining = |rank: &Rank| shoe.count(*rank) as f64 / full_shoe.count(*rank) as f64;
+            let removed = (0..10)
+                .map(Rank::from_index)
+                .filter(|rank| self.tag(*rank) * direction > 0.0 && shoe.count(*rank) > 0)
+                .max_by(|a, b| remaining(a).total_cmp(&remaining(b)));
+            let added = (0..10)
+                .map(Rank::from_index)
+                .filter(|rank| {
+                    self.tag(*rank) * direction < 0.0 && shoe.count(*rank) < full_shoe.count(*rank)
+                })
+                .min_by(|a, b| remaining(a).total_cmp(&remaining(b)));
+
+            match (removed, added) {
+                (None, None) => return Err(Error::UnreachableTrueCount(true_count)),
+                (removed, added) => {
+                    if let Some(rank) = removed {
+                        shoe.remove_card(&Card::from_rank(rank));
+                    }
+                    if let Some(rank) = added {
+                        shoe.add_card(&Card::from_rank(rank));
+                    }
+                }
+            }
+        }
+
+        Ok(shoe)
+    }
+
+    pub fn metrics(&self, effects: &RemovalEffects) -> SystemMetrics {
+        // Playing efficiency follows Griffin: the gain from deviating on the count over the gain
+        // from deviating with the whole composition known, with the shoe dealt halfway. The
+        // change in a decision's gain is the sum of the effects of the cards dealt, taken as
+        // normal, and the count only sees the part of it that correlates with the tags
+        let cards = effects.cards as f64;
+        let dealt = cards / 2.0;
+        let spread = (dealt * (cards - dealt) / (cards - 1.0)).sqrt();
+        let mut perfect_gain = 0.0;
+        let mut count_gain = 0.0;
+        for decision in &effects.decisions {
+            let deviation = spread * deviation(&decision.effects);
+            if deviation > 0.0 {
+                let correlation = correlation(&self.tags, &decision.effects).abs();
+                perfect_gain += decision.probability * deviation_gain(decision.gain, deviation);
+                count_gain +=
+                    decision.probability * deviation_gain(decision.gain, correlation * deviation);
+            }
+        }
+
+        SystemMetrics {
+            betting_correlation: correlation(&self.tags, &effects.effects),
+            playing_efficiency: count_gain / perfect_gain,
+            insurance_correlation: correlation(&self.tags, &INSURANCE_EFFECTS),
+        }
+    }
+}
+
+// Expected gain from switching to the runner-up whenever it becomes the best play, for a
+// decision whose gain is normal with the given mean and deviation
+fn deviation_gain(gain: f64, deviation: f64) -> f64 {
+    if deviation <= 0.0 {
+        return 0.0;
+    }
+    let z = gain / deviation;
+    deviation * normal_density(z) - gain * normal_tail(z)
+}
+
+fn normal_density(z: f64) -> f64 {
+    (-z * z / 2.0).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+// Probability that a standard normal exceeds z, from the approximation of the complementary
+// error function in Abramowitz and Stegun 7.1.26, accurate to about 1e-7
+fn normal_tail(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let polynomial = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let tail = polynomial * (-x * x).exp() / 2.0;
+    if z >= 0.0 { tail } else { 1.0 - tail }
+}
+
+fn mean(values: &[f64; 10]) -> f64 {
+    let total: f64 = RANK_WEIGHTS.iter().sum();
+    values
+        .iter()
+        .zip(RANK_WEIGHTS)
+        .map(|(value, weight)| value * weight)
+        .sum::<f64>()
+        / total
+}
+
+fn covariance(a: &[f64; 10], b: &[f64; 10]) -> f64 {
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let products = std::array::from_fn(|i| (a[i] - mean_a) * (b[i] - mean_b));
+    mean(&products)
+}
+
+fn deviation(values: &[f64; 10]) -> f64 {
+    covariance(values, values).sqrt()
+}
+
+fn correlation(a: &[f64; 10], b: &[f64; 10]) -> f64 {
+    covariance(a, b) / (deviation(a) * deviation(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unbalanced_counts_are_measured_from_the_neutral_count() {
+        let ko = CountingSystem::find("ko").unwrap();
+        let hi_lo = CountingSystem::find("hi-lo").unwrap();
+        assert!(!ko.is_balanced());
+        assert!(hi_lo.is_balanced());
+
+        // A whole deck dealt from two decks leaves the proportions unchanged
+        let mut shoe = CountShoe::new(2).unwrap();
+        for rank in (0..10).map(Rank::from_index) {
+            let cards = if rank == Rank::Ten { 16 } else { 4 };
+            for _ in 0..cards {
+                shoe.remove_card(&Card::from_rank(rank));
+            }
+        }
+        assert_eq!(ko.running_count(&shoe), 4.0);
+        assert_eq!(ko.neutral_running_count(&shoe), 4.0);
+        assert_eq!(ko.true_count(&shoe, DeckEstimation::Exact), 0.0);
+        assert_eq!(hi_lo.neutral_running_count(&shoe), 0.0);
+
+        shoe.remove_card(&Card::from_rank(Rank::Five));
+        let true_count = ko.true_count(&shoe, DeckEstimation::Exact);
+        assert!((true_count - 48.0 / 51.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn only_ko_and_red_7_are_unbalanced() {
+        for system in CountingSystem::all() {
+            let unbalanced = ["ko", "red-7"].contains(&system.name);
+            assert_eq!(system.is_balanced(), !unbalanced, "{}", system.name);
+        }
+        assert_eq!(CountingSystem::find("ko").unwrap().deck_sum(), 4.0);
+        assert_eq!(CountingSystem::find("red-7").unwrap().deck_sum(), 2.0);
+    }
+
+    #[test]
+    fn shoes_reach_the_requested_true_count() {
+        for system in CountingSystem::all() {
+            // A swap moves the running count by at most the spread of the tags
+            let max_tag = system.tags.iter().copied().fold(f64::MIN, f64::max);
+            let min_tag = system.tags.iter().copied().fold(f64::MAX, f64::min);
+            for decks in [1, 2, 6] {
+                for target in -4..=4 {
+                    let shoe = system.shoe_at_true_count(decks, target).unwrap();
+                    assert_eq!(shoe.total(), 26 * decks as usize);
+
+                    let true_count = system.true_count(&shoe, DeckEstimation::Exact);
+                    let step = (max_tag - min_tag) / (decks as f64 / 2.0);
+                    let direction = if target < 0 { -1.0 } else { 1.0 };
+                    let overshoot = (true_count - target as f64) * direction;
+                    assert!(
+                        (0.0..step).contains(&overshoot),
+                        "{} at {target} from {decks} decks: {true_count}",
+                        system.name
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hi_lo_metrics_match_the_published_ones() {
+        // Published for a single deck: betting correlation 0.97, playing efficiency 0.51
+        // and insurance correlation 0.76
+        let effects = crate::removal::tests::one_deck_effects();
+        let metrics = CountingSystem::find("hi-lo").unwrap().metrics(effects);
+        assert!((0.95..=0.99).contains(&metrics.betting_correlation));
+        assert!((0.48..=0.54).contains(&metrics.playing_efficiency));
+        assert!((0.74..=0.78).contains(&metrics.insurance_correlation));
+    }
+}
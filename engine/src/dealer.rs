@@ -0,0 +1,218 @@
+use std::hash::Hash;
+
+use crate::{
+    card::{Card, Rank},
+    game::GameState,
+    hand::Hand,
+    shoe::Shoe,
+    strategy::StrategyGenerator,
+};
+
+// Probability of each final dealer hand, before checking for a natural
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(
+    feature = "tsify",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
+pub struct DealerOutcomes {
+    pub seventeen: f64,
+    pub eighteen: f64,
+    pub nineteen: f64,
+    pub twenty: f64,
+    pub twenty_one: f64,
+    pub natural: f64,
+    pub bust: f64,
+}
+
+impl DealerOutcomes {
+    fn from_hands(hands: &[(Hand, f64)]) -> Self {
+        let total_weight: f64 = hands.iter().map(|(_, weight)| weight).sum();
+        let mut outcomes = Self::default();
+        for (hand, weight) in hands {
+            let probability = match hand.value() {
+                _ if hand.is_blackjack() => &mut outcomes.natural,
+                17 => &mut outcomes.seventeen,
+                18 => &mut outcomes.eighteen,
+                19 => &mut outcomes.nineteen,
+                20 => &mut outcomes.twenty,
+                21 => &mut outcomes.twenty_one,
+                _ => &mut outcomes.bust,
+            };
+            *probability += weight / total_weight;
+        }
+        outcomes
+    }
+
+    fn add_weighted(&mut self, other: &DealerOutcomes, weight: f64) {
+        self.seventeen += other.seventeen * weight;
+        self.eighteen += other.eighteen * weight;
+        self.nineteen += other.nineteen * weight;
+        self.twenty += other.twenty * weight;
+        self.twenty_one += other.twenty_one * weight;
+        self.natural += other.natural * weight;
+        self.bust += other.bust * weight;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(
+    feature = "tsify",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
+pub struct UpcardOutcomes {
+    pub upcard: u8,
+    pub outcomes: DealerOutcomes,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(
+    feature = "tsify",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
+pub struct DealerTable {
+    pub upcards: Vec<UpcardOutcomes>,
+    // Outcomes over every upcard, weighted by how likely each upcard is
+    pub overall: DealerOutcomes,
+}
+
+impl<S: Shoe + Clone + Eq + Hash> StrategyGenerator<S> {
+    pub fn dealer_outcomes(&mut self, dealer_upcard: Card) -> DealerOutcomes {
+        let mut state = GameState {
+            player_hand: Hand::new(),
+            dealer_upcard,
+            shoe: self.shoe().clone(),
+            splits: 0,
+            dealer_peeked: false,
+        };
+        state.shoe.remove_card(&dealer_upcard);
+
+        DealerOutcomes::from_hands(&self.get_dealer_hands(&state))
+    }
+
+    pub fn dealer_table(&mut self) -> DealerTable {
+        let mut upcards = Vec::with_capacity(10);
+        let mut overall = DealerOutcomes::default();
+        for dealer_value in 2..=11 {
+            let dealer_upcard = Card::from_rank(
+                Rank::from_value(dealer_value).expect("dealer values are card values"),
+            );
+            let upcard_probability = self
+                .shoe()
+                .get_draws()
+                .into_iter()
+                .find(|(card, _)| card.rank.value() == dealer_value)
+                .map(|(_, probability)| probability)
+                .unwrap_or_default();
+
+            let outcomes = self.dealer_outcomes(dealer_upcard);
+            overall.add_weighted(&outcomes, upcard_probability);
+            upcards.push(UpcardOutcomes {
+                upcard: dealer_value,
+                outcomes,
+            });
+        }
+
+        DealerTable { upcards, overall }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        options::{GeneratorOptions, PruningStrategy},
+        rules::{Rules, Soft17Rule},
+        shoe::CountShoe,
+    };
+
+    // Solved without pruning, which would spread the pruned hands over the outcomes
+    fn one_deck_generator(dealer_soft_17: Soft17Rule) -> StrategyGenerator<CountShoe> {
+        let rules = Rules {
+            num_decks: 1,
+            dealer_soft_17,
+            ..Rules::default()
+        };
+        let options = GeneratorOptions {
+            pruning: PruningStrategy::None,
+            ..GeneratorOptions::default()
+        };
+        StrategyGenerator::with_options(rules, CountShoe::new(1).unwrap(), options).unwrap()
+    }
+
+    fn total(outcomes: &DealerOutcomes) -> f64 {
+        outcomes.seventeen
+            + outcomes.eighteen
+            + outcomes.nineteen
+            + outcomes.twenty
+            + outcomes.twenty_one
+            + outcomes.natural
+            + outcomes.bust
+    }
+
+    #[test]
+    fn outcomes_of_each_upcard_sum_to_one() {
+        let table = one_deck_generator(Soft17Rule::Stand).dealer_table();
+        assert_eq!(table.upcards.len(), 10);
+        for row in &table.upcards {
+            assert!((total(&row.outcomes) - 1.0).abs() < 1e-12, "{}", row.upcard);
+        }
+        assert!((total(&table.overall) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn hitting_soft_17_only_changes_the_final_totals() {
+        let stand = one_deck_generator(Soft17Rule::Stand).dealer_table();
+        let hit = one_deck_generator(Soft17Rule::Hit).dealer_table();
+        for (stand, hit) in stand.upcards.iter().zip(&hit.upcards) {
+            let upcard = stand.upcard;
+            let (stand, hit) = (&stand.outcomes, &hit.outcomes);
+            assert!((stand.natural - hit.natural).abs() < 1e-12, "{upcard}");
+            // Soft 17 cannot be reached from a seven to a ten
+            if (7..=10).contains(&upcard) {
+                assert_eq!(stand, hit);
+            } else {
+                assert!(hit.seventeen < stand.seventeen, "{upcard}");
+                assert!(hit.bust > stand.bust, "{upcard}");
+            }
+        }
+    }
+
+    #[test]
+    fn no_naturals_once_the_dealer_peeked() {
+        let mut generator = one_deck_generator(Soft17Rule::Stand);
+        for value in [10, 11] {
+            let dealer_upcard = Card::from_rank(Rank::from_value(value).unwrap());
+            let mut state = GameState {
+                player_hand: Hand::new(),
+                dealer_upcard,
+                shoe: generator.shoe().clone(),
+                splits: 0,
+                dealer_peeked: true,
+            };
+            state.shoe.remove_card(&dealer_upcard);
+
+            let peeked = DealerOutcomes::from_hands(&generator.get_dealer_hands(&state));
+            assert_eq!(peeked.natural, 0.0);
+            assert!((total(&peeked) - 1.0).abs() < 1e-12);
+            assert!(generator.dealer_outcomes(dealer_upcard).natural > 0.0);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    hand::Hand,
+    strategy::{PlayerAction, RoundEvs, RoundVariances},
+};
+
+// Outcomes are kept in tenths of a unit so that equal outcomes always merge, every payout
+// is a multiple of a tenth
+const SCALE: f64 = 10.0;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutcomeDistribution {
+    outcomes: BTreeMap<i32, f64>,
+}
+
+impl OutcomeDistribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, outcome: f64, probability: f64) {
+        *self
+            .outcomes
+            .entry((outcome * SCALE).round() as i32)
+            .or_default() += probability;
+    }
+
+    pub(crate) fn add_weighted(&mut self, other: &OutcomeDistribution, weight: f64) {
+        for (outcome, probability) in &other.outcomes {
+            *self.outcomes.entry(*outcome).or_default() += probability * weight;
+        }
+    }
+
+    pub(crate) fn normalize(&mut self, total_weight: f64) {
+        for probability in self.outcomes.values_mut() {
+            *probability /= total_weight;
+        }
+    }
+
+    pub(crate) fn doubled(&self) -> Self {
+        Self {
+            outcomes: self
+                .outcomes
+                .iter()
+                .map(|(outcome, probability)| (outcome * 2, *probability))
+                .collect(),
+        }
+    }
+
+    // Distribution of the sum of two independent outcomes
+    pub(crate) fn convolve(&self, other: &OutcomeDistribution) -> Self {
+        let mut outcomes = BTreeMap::new();
+        for (a, probability_a) in &self.outcomes {
+            for (b, probability_b) in &other.outcomes {
+                *outcomes.entry(a + b).or_default() += probability_a * probability_b;
+            }
+        }
+        Self { outcomes }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    // Outcomes in units with their probability, from the worst to the best
+    pub fn iter(&self) -> impl Iterator<Item = (f64, f64)> {
+        self.outcomes
+            .iter()
+            .map(|(outcome, probability)| (*outcome as f64 / SCALE, *probability))
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.iter()
+            .map(|(outcome, probability)| outcome * probability)
+            .sum()
+    }
+
+    pub fn variance(&self) -> f64 {
+        let mean = self.mean();
+        self.iter()
+            .map(|(outcome, probability)| (outcome - mean).powi(2) * probability)
+            .sum()
+    }
+}
+
+// Outcomes of a hand along with the final dealer hand they were settled against, by its total
+// and whether it is a natural. Each part holds the probability of reaching that dealer hand
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct OutcomesByDealer {
+    parts: BTreeMap<(u8, bool), OutcomeDistribution>,
+}
+
+impl OutcomesByDealer {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn add(&mut self, dealer_hand: &Hand, outcome: f64, probability: f64) {
+        self.parts
+            .entry((dealer_hand.value(), dealer_hand.is_blackjack()))
+            .or_default()
+            .add(outcome, probability);
+    }
+
+    pub(crate) fn add_weighted(&mut self, other: &OutcomesByDealer, weight: f64) {
+        for (dealer, part) in &other.parts {
+            self.parts
+                .entry(*dealer)
+                .or_default()
+                .add_weighted(part, weight);
+        }
+    }
+
+    pub(crate) fn normalize(&mut self, total_weight: f64) {
+        for part in self.parts.values_mut() {
+            part.normalize(total_weight);
+        }
+    }
+
+    pub(crate) fn doubled(&self) -> Self {
+        Self {
+            parts: self
+                .parts
+                .iter()
+                .map(|(dealer, part)| (*dealer, part.doubled()))
+                .collect(),
+        }
+    }
+
+    // Sum of two hands played the same way from a split. Both face the same dealer hand, so
+    // they are only independent once it is known
+    pub(crate) fn split_hands(&self) -> Self {
+        let parts = self
+            .parts
+            .iter()
+            .map(|(dealer, part)| {
+                let probability: f64 = part.outcomes.values().sum();
+                let mut hand = part.clone();
+                hand.normalize(probability);
+                let mut hands = hand.convolve(&hand);
+                for outcome in hands.outcomes.values_mut() {
+                    *outcome *= probability;
+                }
+                (*dealer, hands)
+            })
+            .collect();
+        Self { parts }
+    }
+
+    // Outcomes whatever the dealer ends with
+    pub(crate) fn outcomes(&self) -> OutcomeDistribution {
+        let mut outcomes = OutcomeDistribution::new();
+        for part in self.parts.values() {
+            outcomes.add_weighted(part, 1.0);
+        }
+        outcomes
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.parts.values().map(OutcomeDistribution::len).sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundDistributions {
+    pub hit: OutcomeDistribution,
+    pub stand: OutcomeDistribution,
+    pub double: OutcomeDistribution,
+    pub surrender: Option<OutcomeDistribution>,
+    pub split: Option<OutcomeDistribution>,
+}
+
+impl RoundDistributions {
+    pub fn weighted_average(distributions: &[(RoundDistributions, f64)]) -> Self {
+        let total_weight: f64 = distributions.iter().map(|(_, weight)| weight).sum();
+        let average = |distribution: fn(&RoundDistributions) -> Option<&OutcomeDistribution>| {
+            let mut average = OutcomeDistribution::new();
+            for (distributions, weight) in distributions {
+                average.add_weighted(distribution(distributions)?, *weight);
+            }
+            average.normalize(total_weight);
+            Some(average)
+        };
+
+        Self {
+            hit: average(|distributions| Some(&distributions.hit)).unwrap_or_default(),
+            stand: average(|distributions| Some(&distributions.stand)).unwrap_or_default(),
+            double: average(|distributions| Some(&distributions.double)).unwrap_or_default(),
+            surrender: average(|distributions| distributions.surrender.as_ref()),
+            split: average(|distributions| distributions.split.as_ref()),
+        }
+    }
+
+    pub fn value(&self, action: PlayerAction) -> Option<&OutcomeDistribution> {
+        match action {
+            PlayerAction::Hit => Some(&self.hit),
+            PlayerAction::Stand => Some(&self.stand),
+            PlayerAction::DoubleOrHit | PlayerAction::DoubleOrStand => Some(&self.double),
+            PlayerAction::Split => self.split.as_ref(),
+            PlayerAction::Surrender => self.surrender.as_ref(),
+        }
+    }
+
+    pub fn evs(&self) -> RoundEvs {
+        RoundEvs {
+            hit: self.hit.mean(),
+            stand: self.stand.mean(),
+            double: self.double.mean(),
+            surrender: self.surrender.as_ref().map(OutcomeDistribution::mean),
+            split: self.split.as_ref().map(OutcomeDistribution::mean),
+        }
+    }
+
+    pub fn variances(&self) -> RoundVariances {
+        RoundVariances {
+            hit: self.hit.variance(),
+            stand: self.stand.variance(),
+            double: self.double.variance(),
+            surrender: self.surrender.as_ref().map(OutcomeDistribution::variance),
+            split: self.split.as_ref().map(OutcomeDistribution::variance),
+        }
+    }
+}
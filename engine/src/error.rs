@@ -0,0 +1,102 @@
+use std::fmt;
+
+use crate::{
+    preset::Preset,
+    rules::{MAX_DECKS, MAX_SPLITS},
+    saved::SAVED_TABLES_VERSION,
+    shoe::HandType,
+    strategy::DecisionTable,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    Cancelled,
+    CardNotInShoe(u8),
+    ExactOverflow,
+    InvalidCardValue(u8),
+    InvalidDeckCount(u8),
+    InvalidEpsilon,
+    InvalidMaxDepth(u8),
+    InvalidMaxSplits(u8),
+    InvalidPlayerTotal(u8, HandType),
+    InvalidSavedTable(DecisionTable),
+    ThreadPool,
+    UnknownPreset,
+    UnreachableTrueCount(i8),
+    UnsupportedTablesVersion(u32),
+    ZeroDenominator,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Cancelled => {
+                write!(f, "Generation was cancelled")
+            }
+            Error::CardNotInShoe(value) => {
+                write!(f, "No card of value {value} is left in the shoe")
+            }
+            Error::ExactOverflow => {
+                write!(f, "Exact arithmetic overflowed, the shoe is too large")
+            }
+            Error::InvalidCardValue(value) => {
+                write!(f, "Invalid card value {value}, expected 1 to 11")
+            }
+            Error::InvalidDeckCount(decks) => {
+                write!(
+                    f,
+                    "Invalid number of decks {decks}, expected 1 to {MAX_DECKS}, the most whose \
+                     card counts fit the solver cache keys"
+                )
+            }
+            Error::InvalidEpsilon => {
+                write!(f, "Invalid epsilon, expected at least 0 and below 1")
+            }
+            Error::InvalidMaxDepth(depth) => {
+                write!(f, "Invalid maximum depth {depth}, expected at least 3")
+            }
+            Error::InvalidMaxSplits(splits) => {
+                write!(
+                    f,
+                    "Invalid maximum number of splits {splits}, expected 1 to {MAX_SPLITS}, the \
+                     most the solver cache keys can count"
+                )
+            }
+            Error::InvalidPlayerTotal(value, HandType::Hard) => {
+                write!(f, "Invalid hard total {value}, expected 4 to 21")
+            }
+            Error::InvalidPlayerTotal(value, HandType::Soft) => {
+                write!(f, "Invalid soft total {value}, expected 12 to 21")
+            }
+            Error::InvalidSavedTable(table) => {
+                let name = match table {
+                    DecisionTable::Hard => "hard",
+                    DecisionTable::Soft => "soft",
+                    DecisionTable::Pair => "pair",
+                };
+                write!(f, "Invalid saved {name} table, its size does not match")
+            }
+            Error::ThreadPool => {
+                write!(f, "Failed to start the solver threads")
+            }
+            Error::UnknownPreset => {
+                let names: Vec<_> = Preset::all().map(|preset| preset.name).collect();
+                write!(f, "Unknown preset, expected one of: {}", names.join(", "))
+            }
+            Error::UnreachableTrueCount(true_count) => {
+                write!(f, "True count {true_count} cannot be reached")
+            }
+            Error::UnsupportedTablesVersion(version) => {
+                write!(
+                    f,
+                    "Unsupported saved tables version {version}, expected {SAVED_TABLES_VERSION}"
+                )
+            }
+            Error::ZeroDenominator => {
+                write!(f, "Fraction with a zero denominator")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
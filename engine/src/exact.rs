@@ -0,0 +1,874 @@
+use std::{cmp::Ordering, fmt};
+
+use ahash::AHashMap;
+
+use crate::{
+    card::{Card, Rank},
+    error::Error,
+    game::GameState,
+    hand::{Hand, Outcome},
+    options::{GeneratorOptions, SplitMode},
+    rules::{BlackjackPayout, Rules, SurrenderType},
+    shoe::{CountShoe, Shoe},
+    strategy::{PlayerAction, RoundEvs},
+};
+
+// Fraction kept in lowest terms with a positive denominator, every operation fails instead
+// of overflowing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational {
+        numerator: 0,
+        denominator: 1,
+    };
+
+    pub fn new(numerator: i128, denominator: i128) -> Result<Self, Error> {
+        if denominator == 0 {
+            return Err(Error::ZeroDenominator);
+        }
+        // Reduced by magnitude, as negating `i128::MIN` would overflow
+        let divisor = gcd(numerator.unsigned_abs(), denominator.unsigned_abs());
+        let reduced = |value: i128| {
+            i128::try_from(value.unsigned_abs() / divisor).map_err(|_| Error::ExactOverflow)
+        };
+        let magnitude = reduced(numerator)?;
+        Ok(Self {
+            numerator: if (numerator < 0) != (denominator < 0) {
+                -magnitude
+            } else {
+                magnitude
+            },
+            denominator: reduced(denominator)?,
+        })
+    }
+
+    pub fn from_integer(value: i128) -> Self {
+        Self {
+            numerator: value,
+            denominator: 1,
+        }
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.denominator
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    pub fn checked_add(self, other: Rational) -> Result<Rational, Error> {
+        let divisor = common_divisor(self.denominator, other.denominator)?;
+        let left = other.denominator / divisor;
+        let right = self.denominator / divisor;
+        let numerator = self
+            .numerator
+            .checked_mul(left)
+            .zip(other.numerator.checked_mul(right))
+            .and_then(|(a, b)| a.checked_add(b));
+        let denominator = self.denominator.checked_mul(left);
+        match (numerator, denominator) {
+            (Some(numerator), Some(denominator)) => Rational::new(numerator, denominator),
+            _ => Err(Error::ExactOverflow),
+        }
+    }
+
+    pub fn checked_mul(self, other: Rational) -> Result<Rational, Error> {
+        // Cancel across the two fractions first so intermediate products stay small
+        let a = common_divisor(self.numerator, other.denominator)?;
+        let b = common_divisor(other.numerator, self.denominator)?;
+        let numerator = (self.numerator / a).checked_mul(other.numerator / b);
+        let denominator = (self.denominator / b).checked_mul(other.denominator / a);
+        match (numerator, denominator) {
+            (Some(numerator), Some(denominator)) => Rational::new(numerator, denominator),
+            _ => Err(Error::ExactOverflow),
+        }
+    }
+}
+
+impl Ord for Rational {
+    // Compares the continued fraction expansions, which cannot overflow unlike cross
+    // multiplying
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (mut a, mut b) = (self.numerator, self.denominator);
+        let (mut c, mut d) = (other.numerator, other.denominator);
+        let mut reversed = false;
+        loop {
+            let (x, y) = (a.div_euclid(b), c.div_euclid(d));
+            let (r, s) = (a.rem_euclid(b), c.rem_euclid(d));
+            let ordering = match (r, s) {
+                _ if x != y => x.cmp(&y),
+                (0, 0) => Ordering::Equal,
+                (0, _) => Ordering::Less,
+                (_, 0) => Ordering::Greater,
+                _ => {
+                    // Comparing the reciprocals of the remainders flips the order
+                    (a, b, c, d) = (b, r, d, s);
+                    reversed = !reversed;
+                    continue;
+                }
+            };
+            return if reversed {
+                ordering.reverse()
+            } else {
+                ordering
+            };
+        }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    // Only zero over zero would leave nothing to divide by
+    a.max(1)
+}
+
+// Positive divisor of both values, which fits an `i128` whenever one of them is a denominator
+fn common_divisor(a: i128, b: i128) -> Result<i128, Error> {
+    i128::try_from(gcd(a.unsigned_abs(), b.unsigned_abs())).map_err(|_| Error::ExactOverflow)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ExactRoundEvs {
+    pub hit: Rational,
+    pub stand: Rational,
+    pub double: Rational,
+    pub surrender: Option<Rational>,
+    pub split: Option<Rational>,
+}
+
+impl ExactRoundEvs {
+    pub fn to_f64(&self) -> RoundEvs {
+        RoundEvs {
+            hit: self.hit.to_f64(),
+            stand: self.stand.to_f64(),
+            double: self.double.to_f64(),
+            surrender: self.surrender.map(|ev| ev.to_f64()),
+            split: self.split.map(|ev| ev.to_f64()),
+        }
+    }
+}
+
+const THREE_TO_TWO: Rational = Rational {
+    numerator: 3,
+    denominator: 2,
+};
+const SIX_TO_FIVE: Rational = Rational {
+    numerator: 6,
+    denominator: 5,
+};
+const HALF_LOSS: Rational = Rational {
+    numerator: -1,
+    denominator: 2,
+};
+
+type DealerHandKey = (Card, CountShoe, bool);
+
+// Same model as `StrategyGenerator`, including dealer cards being drawn from the shoe left
+// by the player, but without pruning and with exact arithmetic. Denominators grow with the
+// number of cards drawn, so it is only practical for small shoes, and reports an overflow
+// otherwise. Of the generator options only the maximum depth and the split mode apply
+pub struct ExactGenerator {
+    rules: Rules,
+    shoe: CountShoe,
+    options: GeneratorOptions,
+    hit_cache: AHashMap<GameState<CountShoe>, Rational>,
+    stand_cache: AHashMap<GameState<CountShoe>, Rational>,
+    double_cache: AHashMap<GameState<CountShoe>, Rational>,
+    split_cache: AHashMap<GameState<CountShoe>, Rational>,
+    dealer_hand_cache: AHashMap<DealerHandKey, Vec<(Hand, Rational)>>,
+}
+
+impl ExactGenerator {
+    pub fn new(rules: Rules, shoe: CountShoe) -> Result<Self, Error> {
+        Self::with_options(rules, shoe, GeneratorOptions::default())
+    }
+
+    pub fn with_options(
+        rules: Rules,
+        shoe: CountShoe,
+        options: GeneratorOptions,
+    ) -> Result<Self, Error> {
+        rules.validate()?;
+        options.validate()?;
+
+        Ok(Self {
+            rules,
+            shoe,
+            options,
+            hit_cache: AHashMap::new(),
+            stand_cache: AHashMap::new(),
+            double_cache: AHashMap::new(),
+            split_cache: AHashMap::new(),
+            dealer_hand_cache: AHashMap::new(),
+        })
+    }
+
+    pub fn eval_round(
+        &mut self,
+        player_hand: Hand,
+        dealer_upcard: Card,
+        is_pair: bool,
+    ) -> Result<ExactRoundEvs, Error> {
+        let mut state = GameState {
+            dealer_upcard,
+            player_hand,
+            shoe: self.shoe.clone(),
+            splits: 0,
+            dealer_peeked: self.rules.dealer_checks_blackjack(&dealer_upcard),
+        };
+
+        state.shoe.remove_card(&dealer_upcard);
+        for card in player_hand.cards() {
+            state.shoe.remove_card(&card);
+        }
+
+        Ok(ExactRoundEvs {
+            hit: self.expected_value_hit(&mut state)?,
+            stand: self.expected_value_stand(&mut state)?,
+            double: self.expected_value_double(&mut state)?,
+            surrender: self.expected_value_surrender(&state)?,
+            split: if is_pair {
+                Some(self.expected_value_split(&mut state)?)
+            } else {
+                None
+            },
+        })
+    }
+
+    // Past the maximum depth the player stands, as in `StrategyGenerator`
+    fn can_hit(&self, hand: &Hand) -> bool {
+        hand.value() < 21
+            && self
+                .options
+                .max_depth
+                .is_none_or(|depth| hand.size() < depth)
+    }
+
+    fn get_dealer_hands(
+        &mut self,
+        state: &GameState<CountShoe>,
+    ) -> Result<Vec<(Hand, Rational)>, Error> {
+        let key = (state.dealer_upcard, state.shoe.clone(), state.dealer_peeked);
+        if let Some(cached) = self.dealer_hand_cache.get(&key) {
+            return Ok(cached.clone());
+        }
+
+        let draws = draws(&state.shoe)?;
+        let mut map = AHashMap::<(u8, bool), (Hand, Rational)>::with_capacity(16);
+
+        let mut stack = Vec::with_capacity(100);
+        let mut start_hand = Hand::new();
+        start_hand.add_card(&state.dealer_upcard);
+        if state.dealer_peeked {
+            // The dealer checked for blackjack, so the hole card cannot complete a natural
+            let mut hands = Vec::new();
+            let mut total = 0;
+            for (card, count) in counts(&state.shoe) {
+                let mut hand = start_hand;
+                hand.add_card(&card);
+                if !hand.is_blackjack() {
+                    total += count;
+                    hands.push((hand, count));
+                }
+            }
+            for (hand, count) in hands {
+                stack.push((hand, Rational::new(count as i128, total as i128)?));
+            }
+        } else {
+            stack.push((start_hand, Rational::from_integer(1)));
+        }
+
+        while let Some((hand, weight)) = stack.pop() {
+            if self.rules.dealer_must_stand(&hand) {
+                let entry = map
+                    .entry((hand.value(), hand.is_blackjack()))
+                    .or_insert((hand, Rational::ZERO));
+                entry.1 = entry.1.checked_add(weight)?;
+                continue;
+            }
+
+            for (card, draw_weight) in &draws {
+                let mut next_hand = hand;
+                next_hand.add_card(card);
+                stack.push((next_hand, weight.checked_mul(*draw_weight)?));
+            }
+        }
+
+        let result: Vec<_> = map.into_values().collect();
+        self.dealer_hand_cache.insert(key, result.clone());
+        Ok(result)
+    }
+
+    fn expected_value_stand(
+        &mut self,
+        state: &mut GameState<CountShoe>,
+    ) -> Result<Rational, Error> {
+        if let Some(item) = self.stand_cache.get(state) {
+            return Ok(*item);
+        }
+        let mut ev = Rational::ZERO;
+        for (dealer_hand, weight) in self.get_dealer_hands(state)? {
+            let outcome = self.stand_outcome(state, &dealer_hand);
+            ev = ev.checked_add(outcome.checked_mul(weight)?)?;
+        }
+
+        self.stand_cache.insert(state.clone(), ev);
+        Ok(ev)
+    }
+
+    fn stand_outcome(&self, state: &GameState<CountShoe>, dealer_hand: &Hand) -> Rational {
+        let outcome = if state.splits > 0 {
+            Hand::compare_split(&state.player_hand, dealer_hand)
+        } else {
+            Hand::compare(&state.player_hand, dealer_hand)
+        };
+        match outcome {
+            Outcome::Win => {
+                if state.splits == 0 && state.player_hand.is_blackjack() {
+                    match self.rules.blackjack_payout {
+                        BlackjackPayout::Ratio3to2 => THREE_TO_TWO,
+                        BlackjackPayout::Ratio6to5 => SIX_TO_FIVE,
+                    }
+                } else {
+                    Rational::from_integer(1)
+                }
+            }
+            Outcome::Push => Rational::ZERO,
+            Outcome::Lose => Rational::from_integer(-1),
+        }
+    }
+
+    fn expected_value_hit(&mut self, state: &mut GameState<CountShoe>) -> Result<Rational, Error> {
+        if let Some(item) = self.hit_cache.get(state) {
+            return Ok(*item);
+        }
+        let mut total_ev = Rational::ZERO;
+
+        for (card, draw_weight) in draws(&state.shoe)? {
+            state.player_hand.add_card(&card);
+            let ev = if state.player_hand.is_bust() {
+                Ok(Rational::from_integer(-1))
+            } else {
+                state.shoe.remove_card(&card);
+                let ev = if !self.can_hit(&state.player_hand) {
+                    self.expected_value_stand(state)
+                } else {
+                    self.expected_value_hit(state)
+                        .and_then(|ev_hit| Ok(ev_hit.max(self.expected_value_stand(state)?)))
+                };
+                state.shoe.add_card(&card);
+                ev
+            };
+            state.player_hand.remove_card(&card);
+
+            total_ev = total_ev.checked_add(ev?.checked_mul(draw_weight)?)?;
+        }
+
+        self.hit_cache.insert(state.clone(), total_ev);
+        Ok(total_ev)
+    }
+
+    fn expected_value_double(
+        &mut self,
+        state: &mut GameState<CountShoe>,
+    ) -> Result<Rational, Error> {
+        if let Some(item) = self.double_cache.get(state) {
+            return Ok(*item);
+        }
+        let mut total_ev = Rational::ZERO;
+
+        for (card, draw_weight) in draws(&state.shoe)? {
+            state.player_hand.add_card(&card);
+            state.shoe.remove_card(&card);
+            let ev = self.expected_value_stand(state);
+            state.shoe.add_card(&card);
+            state.player_hand.remove_card(&card);
+
+            let ev = ev?.checked_mul(Rational::from_integer(2))?;
+            total_ev = total_ev.checked_add(ev.checked_mul(draw_weight)?)?;
+        }
+
+        self.double_cache.insert(state.clone(), total_ev);
+        Ok(total_ev)
+    }
+
+    fn expected_value_split(
+        &mut self,
+        state: &mut GameState<CountShoe>,
+    ) -> Result<Rational, Error> {
+        if let Some(item) = self.split_cache.get(state) {
+            return Ok(*item);
+        }
+        state.split();
+        // The state is restored before any overflow is reported
+        let first = self.split_draws_value(state);
+        let ev = match self.options.split {
+            // Hands created by resplitting are always treated as independent
+            SplitMode::Sequential if state.splits == 1 => {
+                first.and_then(|first| first.checked_add(self.second_split_hand_value(state)?))
+            }
+            _ => first.and_then(|first| first.checked_mul(Rational::from_integer(2))),
+        };
+        state.unsplit();
+
+        let ev = ev?;
+        self.split_cache.insert(state.clone(), ev);
+        Ok(ev)
+    }
+
+    // Value of one split hand, averaged over its second card
+    fn split_draws_value(&mut self, state: &mut GameState<CountShoe>) -> Result<Rational, Error> {
+        let mut total_ev = Rational::ZERO;
+        for (card, draw_weight) in draws(&state.shoe)? {
+            let initial_value = state.player_hand.value();
+            state.player_hand.add_card(&card);
+            state.shoe.remove_card(&card);
+            let ev = self.split_hand_action(state, &card, initial_value);
+            state.shoe.add_card(&card);
+            state.player_hand.remove_card(&card);
+
+            total_ev = total_ev.checked_add(ev?.1.checked_mul(draw_weight)?)?;
+        }
+        Ok(total_ev)
+    }
+
+    // Best play of a split hand once its second card is drawn, ties broken as in
+    // `StrategyGenerator` so both follow the same hands when splitting sequentially
+    fn split_hand_action(
+        &mut self,
+        state: &mut GameState<CountShoe>,
+        card: &Card,
+        initial_value: u8,
+    ) -> Result<(PlayerAction, Rational), Error> {
+        let hit = self.expected_value_hit(state)?;
+        let stand = self.expected_value_stand(state)?;
+        let (mut action, mut ev) = if hit > stand {
+            (PlayerAction::Hit, hit)
+        } else {
+            (PlayerAction::Stand, stand)
+        };
+        if self.rules.double_after_split_allowed {
+            let double = self.expected_value_double(state)?;
+            if double > ev {
+                (action, ev) = (PlayerAction::DoubleOrHit, double);
+            }
+        }
+        if card.rank.value() == initial_value && state.splits < self.rules.max_splits {
+            let split = self.expected_value_split(state)?;
+            if split > ev {
+                (action, ev) = (PlayerAction::Split, split);
+            }
+        }
+        Ok((action, ev))
+    }
+
+    // Value of the second split hand, drawn from each shoe the first one can leave
+    fn second_split_hand_value(
+        &mut self,
+        state: &mut GameState<CountShoe>,
+    ) -> Result<Rational, Error> {
+        let mut shoes = Vec::new();
+        self.collect_split_hand_shoes(state, &mut shoes)?;
+
+        let mut second = state.clone();
+        let mut total_ev = Rational::ZERO;
+        for (shoe, probability) in shoes {
+            second.shoe = shoe;
+            let ev = self.split_draws_value(&mut second)?;
+            total_ev = total_ev.checked_add(ev.checked_mul(probability)?)?;
+        }
+        Ok(total_ev)
+    }
+
+    fn collect_split_hand_shoes(
+        &mut self,
+        state: &mut GameState<CountShoe>,
+        shoes: &mut Vec<(CountShoe, Rational)>,
+    ) -> Result<(), Error> {
+        for (card, draw_weight) in draws(&state.shoe)? {
+            let initial_value = state.player_hand.value();
+            state.player_hand.add_card(&card);
+            state.shoe.remove_card(&card);
+            let result = self
+                .split_hand_action(state, &card, initial_value)
+                .and_then(|(action, _)| match action {
+                    PlayerAction::Hit => self.collect_hit_shoes(state, draw_weight, shoes),
+                    PlayerAction::DoubleOrHit => {
+                        for (card, next_weight) in draws(&state.shoe)? {
+                            let probability = draw_weight.checked_mul(next_weight)?;
+                            state.shoe.remove_card(&card);
+                            let result = record_shoe(shoes, &state.shoe, probability);
+                            state.shoe.add_card(&card);
+                            result?;
+                        }
+                        Ok(())
+                    }
+                    // Cards drawn by hands created by resplitting are not followed
+                    _ => record_shoe(shoes, &state.shoe, draw_weight),
+                });
+            state.shoe.add_card(&card);
+            state.player_hand.remove_card(&card);
+            result?;
+        }
+        Ok(())
+    }
+
+    // Follows a hand that hits until it stands or busts, along with the probability of each
+    // shoe it leaves
+    fn collect_hit_shoes(
+        &mut self,
+        state: &mut GameState<CountShoe>,
+        probability: Rational,
+        shoes: &mut Vec<(CountShoe, Rational)>,
+    ) -> Result<(), Error> {
+        for (card, draw_weight) in draws(&state.shoe)? {
+            let probability = probability.checked_mul(draw_weight)?;
+            state.player_hand.add_card(&card);
+            state.shoe.remove_card(&card);
+            let result = self.keeps_hitting(state).and_then(|hitting| {
+                if hitting {
+                    self.collect_hit_shoes(state, probability, shoes)
+                } else {
+                    record_shoe(shoes, &state.shoe, probability)
+                }
+            });
+            state.shoe.add_card(&card);
+            state.player_hand.remove_card(&card);
+            result?;
+        }
+        Ok(())
+    }
+
+    fn keeps_hitting(&mut self, state: &mut GameState<CountShoe>) -> Result<bool, Error> {
+        if state.player_hand.is_bust() || !self.can_hit(&state.player_hand) {
+            return Ok(false);
+        }
+        Ok(self.expected_value_hit(state)? > self.expected_value_stand(state)?)
+    }
+
+    // None when the rules do not allow surrendering
+    fn expected_value_surrender(
+        &self,
+        state: &GameState<CountShoe>,
+    ) -> Result<Option<Rational>, Error> {
+        match self.rules.surrender {
+            SurrenderType::None => Ok(None),
+            SurrenderType::Early => Ok(Some(HALF_LOSS)),
+            SurrenderType::Late if state.dealer_peeked => Ok(Some(HALF_LOSS)),
+            SurrenderType::Late => {
+                let mut hand = Hand::new();
+                hand.add_card(&state.dealer_upcard);
+
+                let mut total_ev = Rational::ZERO;
+                for (card, draw_weight) in draws(&state.shoe)? {
+                    hand.add_card(&card);
+                    let ev = if hand.is_blackjack() {
+                        Rational::from_integer(-1)
+                    } else {
+                        HALF_LOSS
+                    };
+                    hand.remove_card(&card);
+
+                    total_ev = total_ev.checked_add(ev.checked_mul(draw_weight)?)?;
+                }
+                Ok(Some(total_ev))
+            }
+        }
+    }
+}
+
+fn counts(shoe: &CountShoe) -> impl Iterator<Item = (Card, usize)> {
+    (0..10)
+        .map(Rank::from_index)
+        .map(|rank| (Card::from_rank(rank), shoe.count(rank)))
+        .filter(|(_, count)| *count > 0)
+}
+
+fn draws(shoe: &CountShoe) -> Result<Vec<(Card, Rational)>, Error> {
+    let total = shoe.total() as i128;
+    counts(shoe)
+        .map(|(card, count)| Ok((card, Rational::new(count as i128, total)?)))
+        .collect()
+}
+
+fn record_shoe(
+    shoes: &mut Vec<(CountShoe, Rational)>,
+    shoe: &CountShoe,
+    probability: Rational,
+) -> Result<(), Error> {
+    match shoes.iter_mut().find(|(other, _)| other == shoe) {
+        Some((_, total)) => *total = total.checked_add(probability)?,
+        None => shoes.push((shoe.clone(), probability)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{options::PruningStrategy, strategy::StrategyGenerator};
+
+    fn card(value: u8) -> Card {
+        Card::from_rank(Rank::from_value(value).unwrap())
+    }
+
+    fn fraction(numerator: i128, denominator: i128) -> Rational {
+        Rational::new(numerator, denominator).unwrap()
+    }
+
+    #[test]
+    fn rational_reduces_to_lowest_terms() {
+        let value = fraction(6, -4);
+        assert_eq!((value.numerator(), value.denominator()), (-3, 2));
+        assert_eq!(fraction(-6, -4), fraction(3, 2));
+        assert_eq!(fraction(0, -7), Rational::ZERO);
+        assert_eq!(fraction(i128::MIN, 2).numerator(), i128::MIN / 2);
+        assert_eq!(fraction(9, 3).to_string(), "3");
+        assert_eq!(fraction(-2, 6).to_string(), "-1/3");
+    }
+
+    #[test]
+    fn rational_rejects_invalid_fractions() {
+        assert_eq!(Rational::new(1, 0), Err(Error::ZeroDenominator));
+        // The magnitude of `i128::MIN` does not fit once the sign is moved
+        assert_eq!(Rational::new(i128::MIN, 1), Err(Error::ExactOverflow));
+        assert_eq!(Rational::new(1, i128::MIN), Err(Error::ExactOverflow));
+    }
+
+    #[test]
+    fn rational_arithmetic() {
+        assert_eq!(
+            fraction(1, 6).checked_add(fraction(1, 3)),
+            Ok(fraction(1, 2))
+        );
+        assert_eq!(
+            fraction(1, 2).checked_add(fraction(-1, 2)),
+            Ok(Rational::ZERO)
+        );
+        assert_eq!(
+            fraction(2, 3).checked_mul(fraction(9, 4)),
+            Ok(fraction(3, 2))
+        );
+        assert_eq!(
+            fraction(-2, 3).checked_mul(fraction(3, -2)),
+            Ok(fraction(1, 1))
+        );
+        assert_eq!(
+            Rational::ZERO.checked_mul(fraction(5, 7)),
+            Ok(Rational::ZERO)
+        );
+
+        let large = Rational::from_integer(i128::MAX);
+        assert_eq!(
+            large.checked_add(Rational::from_integer(1)),
+            Err(Error::ExactOverflow)
+        );
+        assert_eq!(
+            large.checked_mul(Rational::from_integer(2)),
+            Err(Error::ExactOverflow)
+        );
+        assert_eq!(
+            fraction(1, i128::MAX).checked_add(fraction(1, i128::MAX - 1)),
+            Err(Error::ExactOverflow)
+        );
+    }
+
+    #[test]
+    fn rational_comparison() {
+        let ordered = [
+            Rational::from_integer(-2),
+            fraction(-1, 2),
+            fraction(-1, 3),
+            Rational::ZERO,
+            fraction(333, 1000),
+            fraction(1, 3),
+            fraction(334, 1000),
+            fraction(1, 2),
+            fraction(i128::MAX, i128::MAX - 1),
+        ];
+        for (i, a) in ordered.iter().enumerate() {
+            for (j, b) in ordered.iter().enumerate() {
+                assert_eq!(a.cmp(b), i.cmp(&j), "{a} and {b}");
+            }
+        }
+        assert_eq!(fraction(2, 4).cmp(&fraction(1, 2)), Ordering::Equal);
+    }
+
+    fn assert_within(exact: ExactRoundEvs, evs: RoundEvs, bound: f64) {
+        let exact = exact.to_f64();
+        let pairs = [
+            (Some(exact.hit), Some(evs.hit)),
+            (Some(exact.stand), Some(evs.stand)),
+            (Some(exact.double), Some(evs.double)),
+            (exact.surrender, evs.surrender),
+            (exact.split, evs.split),
+        ];
+        for (exact, ev) in pairs {
+            assert_eq!(exact.is_some(), ev.is_some());
+            if let (Some(exact), Some(ev)) = (exact, ev) {
+                assert!(
+                    (exact - ev).abs() <= bound,
+                    "exact {exact} and {ev} differ by more than {bound}"
+                );
+            }
+        }
+    }
+
+    fn compare(
+        rules: Rules,
+        shoe: CountShoe,
+        options: GeneratorOptions,
+        hand: [u8; 2],
+        upcard: u8,
+    ) {
+        let player_hand = Hand::from_cards(&hand.map(card));
+        let is_pair = player_hand.is_pair();
+        let mut generator = StrategyGenerator::with_options(rules, shoe.clone(), options).unwrap();
+        let (evs, error) = generator.eval_round_with_error(player_hand, card(upcard), is_pair);
+        let mut exact = ExactGenerator::with_options(rules, shoe, options).unwrap();
+        let exact_evs = exact
+            .eval_round(player_hand, card(upcard), is_pair)
+            .unwrap();
+        // The bound only covers pruning, rounding adds a little more
+        assert_within(exact_evs, evs, error + 1e-12);
+    }
+
+    #[test]
+    fn agrees_with_the_generator_on_one_deck() {
+        let rules = Rules {
+            num_decks: 1,
+            surrender: SurrenderType::Late,
+            ..Rules::default()
+        };
+        let shoe = CountShoe::new(1).unwrap();
+        // Soft hands draw long enough to overflow on a whole deck
+        for (hand, upcard) in [([10, 6], 10), ([5, 6], 6), ([9, 7], 7), ([4, 8], 5)] {
+            compare(
+                rules,
+                shoe.clone(),
+                GeneratorOptions::default(),
+                hand,
+                upcard,
+            );
+        }
+    }
+
+    #[test]
+    fn pruning_error_covers_the_difference() {
+        let rules = Rules {
+            num_decks: 1,
+            ..Rules::default()
+        };
+        let shoe = CountShoe::new(1).unwrap();
+        for epsilon in [1e-5, 1e-3, 1e-2] {
+            let options = GeneratorOptions {
+                epsilon,
+                ..GeneratorOptions::default()
+            };
+            for (hand, upcard) in [([10, 2], 3), ([9, 7], 11)] {
+                let player_hand = Hand::from_cards(&hand.map(card));
+                let mut generator =
+                    StrategyGenerator::with_options(rules, shoe.clone(), options).unwrap();
+                let (evs, error) =
+                    generator.eval_round_with_error(player_hand, card(upcard), false);
+                let mut exact = ExactGenerator::with_options(rules, shoe.clone(), options).unwrap();
+                let exact_evs = exact.eval_round(player_hand, card(upcard), false).unwrap();
+
+                // Pruning moves the EVs, but never past the reported bound
+                assert!((exact_evs.stand.to_f64() - evs.stand).abs() > 1e-6);
+                assert_within(exact_evs, evs, error);
+            }
+        }
+    }
+
+    #[test]
+    fn splits_agree_with_the_generator() {
+        let rules = Rules {
+            num_decks: 1,
+            max_splits: 1,
+            ..Rules::default()
+        };
+        // Exact sequential splits overflow on a whole deck, what is left still holds the pairs
+        // and upcards below
+        let mut shoe = CountShoe::new(1).unwrap();
+        for (value, removed) in [
+            (2, 2),
+            (3, 3),
+            (4, 3),
+            (5, 3),
+            (6, 3),
+            (7, 3),
+            (8, 1),
+            (9, 1),
+            (10, 10),
+            (11, 3),
+        ] {
+            for _ in 0..removed {
+                shoe.remove_card(&card(value));
+            }
+        }
+
+        for split in [SplitMode::Independent, SplitMode::Sequential] {
+            let options = GeneratorOptions {
+                pruning: PruningStrategy::None,
+                split,
+                ..GeneratorOptions::default()
+            };
+            for (value, upcard) in [(8, 6), (2, 7), (9, 9)] {
+                compare(rules, shoe.clone(), options, [value, value], upcard);
+            }
+        }
+    }
+
+    #[test]
+    fn stands_past_the_maximum_depth() {
+        let rules = Rules {
+            num_decks: 1,
+            ..Rules::default()
+        };
+        let options = GeneratorOptions {
+            pruning: PruningStrategy::None,
+            max_depth: Some(3),
+            ..GeneratorOptions::default()
+        };
+        let shoe = CountShoe::new(1).unwrap();
+        compare(rules, shoe.clone(), options, [2, 3], 10);
+
+        // Hitting once and standing is all a third card leaves
+        let player_hand = Hand::from_cards(&[card(2), card(3)]);
+        let mut exact = ExactGenerator::with_options(rules, shoe, options).unwrap();
+        let evs = exact.eval_round(player_hand, card(10), false).unwrap();
+        assert_eq!(
+            evs.hit.checked_mul(Rational::from_integer(2)),
+            Ok(evs.double)
+        );
+    }
+}
@@ -1,4 +1,17 @@
-use crate::{card::Card, hand::Hand, shoe::Shoe};
+use crate::{
+    card::Card,
+    hand::Hand,
+    options::{GeneratorOptions, PruningStrategy},
+    shoe::Shoe,
+};
+
+// Bits of a `StateKey` above the packed player hand
+const UPCARD_SHIFT: u32 = 50;
+const SPLITS_SHIFT: u32 = 54;
+const PEEKED_SHIFT: u32 = 57;
+const WEIGHT_SHIFT: u32 = 58;
+pub(crate) const SPLITS_BITS: u32 = PEEKED_SHIFT - SPLITS_SHIFT;
+const MAX_WEIGHT_EXPONENT: u64 = (1 << (u64::BITS - WEIGHT_SHIFT)) - 1;
 
 #[derive(Clone, Hash, PartialEq, Eq)]
 pub struct GameState<S: Shoe> {
@@ -6,6 +19,7 @@ pub struct GameState<S: Shoe> {
     pub dealer_upcard: Card,
     pub shoe: S,
     pub splits: u8,
+    pub dealer_peeked: bool,
 }
 
 impl<S: Shoe> GameState<S> {
@@ -18,4 +32,67 @@ impl<S: Shoe> GameState<S> {
         self.player_hand.unsplit();
         self.splits -= 1;
     }
+
+    // Hands are keyed by their composition when it changes the shoe they leave, otherwise
+    // hands with the same total share their entries.
+    //
+    // Also returns the branch weight to solve the state with. Branch pruning solves it with the
+    // power of two at or above the weight and keys it by the exponent, so the value of a state
+    // does not depend on the branch that reached it first
+    pub(crate) fn key(&self, options: &GeneratorOptions, branch_weight: f64) -> (StateKey, f64) {
+        let hand = if S::REMOVES_CARDS {
+            self.player_hand.packed_counts()
+        } else {
+            self.player_hand.packed_total(options.max_depth)
+        };
+        let exponent = match options.pruning {
+            PruningStrategy::Branch => weight_exponent(branch_weight),
+            _ => 0,
+        };
+        let key = StateKey {
+            round: hand
+                | (self.dealer_upcard.rank.index() as u64) << UPCARD_SHIFT
+                | u64::from(self.splits) << SPLITS_SHIFT
+                | u64::from(self.dealer_peeked) << PEEKED_SHIFT
+                | exponent << WEIGHT_SHIFT,
+            shoe: self.shoe.key(),
+        };
+        let branch_weight = match options.pruning {
+            PruningStrategy::Branch => 0.5f64.powi(exponent as i32),
+            _ => branch_weight,
+        };
+        (key, branch_weight)
+    }
+}
+
+// Compact stand-in for a `GameState` in caches, everything but the shoe is packed in a single
+// integer and the shoe is replaced by its own key
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+pub(crate) struct StateKey {
+    pub(crate) round: u64,
+    pub(crate) shoe: u64,
+}
+
+impl StateKey {
+    #[cfg(feature = "parallel")]
+    pub(crate) fn upcard_index(&self) -> usize {
+        ((self.round >> UPCARD_SHIFT) & 0xf) as usize
+    }
+}
+
+// Exponent of the largest power of two at most one that is at or above a weight, capped by
+// what the key can hold
+fn weight_exponent(weight: f64) -> u64 {
+    if weight >= 1.0 {
+        return 0;
+    }
+    let bits = weight.to_bits();
+    let exponent = 1023 - ((bits >> 52) & 0x7ff);
+    // Weights that are not a power of two are rounded up to the next one
+    let exponent = if bits & ((1 << 52) - 1) == 0 {
+        exponent
+    } else {
+        exponent - 1
+    };
+    exponent.min(MAX_WEIGHT_EXPONENT)
 }
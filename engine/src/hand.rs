@@ -1,4 +1,13 @@
-use crate::card::{Card, Rank};
+use std::iter;
+
+use crate::{
+    card::{Card, Rank},
+    error::Error,
+};
+
+// Per-rank card counts are packed in 5 bits each, indexed like `Rank::index`
+const RANK_BITS: u32 = 5;
+const RANK_MASK: u64 = (1 << RANK_BITS) - 1;
 
 #[derive(Clone, Copy, Hash, PartialEq, Eq)]
 pub struct Hand {
@@ -6,7 +15,15 @@ pub struct Hand {
     value: u8,
     aces: u8,
     soft_aces: u8,
+    cards: u64,
+}
+
+impl Default for Hand {
+    fn default() -> Self {
+        Self::new()
+    }
 }
+
 pub enum Outcome {
     Win,
     Push,
@@ -20,19 +37,29 @@ impl Hand {
             value: 0,
             aces: 0,
             soft_aces: 0,
+            cards: 0,
         }
     }
 
+    pub fn from_cards(cards: &[Card]) -> Self {
+        let mut hand = Self::new();
+        for card in cards {
+            hand.add_card(card);
+        }
+        hand
+    }
+
     pub fn add_card(&mut self, card: &Card) {
         let value = card.rank.value();
         self.size += 1;
         self.value += value;
+        self.cards += 1 << (card.rank.index() as u32 * RANK_BITS);
         if card.rank == Rank::Ace {
             self.aces += 1;
             self.soft_aces += 1;
         }
 
-        if self.value > 21 && self.soft_aces > 0 {
+        while self.value > 21 && self.soft_aces > 0 {
             self.value -= 10;
             self.soft_aces -= 1;
         }
@@ -42,6 +69,7 @@ impl Hand {
         let value = card.rank.value();
 
         self.size -= 1;
+        self.cards -= 1 << (card.rank.index() as u32 * RANK_BITS);
 
         if card.rank == Rank::Ace {
             self.aces -= 1;
@@ -62,25 +90,72 @@ impl Hand {
     }
 
     pub fn split(&mut self) {
-        if self.aces == 2 {
-            self.aces = 1;
-            self.soft_aces = 1;
-            self.value = 11;
-        } else {
-            self.value /= 2;
-        }
-        self.size = 1;
+        let card = self.cards().next().expect("only pairs can be split");
+        *self = Self::from_cards(&[card]);
     }
 
     pub fn unsplit(&mut self) {
-        if self.aces == 1 {
-            self.aces = 2;
-            self.soft_aces = 1;
-            self.value = 12;
-        } else {
-            self.value *= 2;
+        let card = self
+            .cards()
+            .next()
+            .expect("only split hands can be unsplit");
+        self.add_card(&card);
+    }
+
+    // Card counts alone determine the hand, packed in the low 50 bits
+    pub(crate) fn packed_counts(&self) -> u64 {
+        self.cards
+    }
+
+    // What decides how a hand plays when its cards do not change the shoe: its total and
+    // softness, whether it is a natural, the pair it can split and, when hitting stops at a
+    // maximum depth, how many cards it holds up to that depth. Fits in the low 50 bits like
+    // `packed_counts`
+    pub(crate) fn packed_total(&self, max_depth: Option<u8>) -> u64 {
+        let pair = (0..10)
+            .find(|&index| self.size == 2 && (self.cards >> (index * RANK_BITS)) & RANK_MASK == 2)
+            .map_or(0, |index| u64::from(index) + 1);
+        let size = max_depth.map_or(0, |depth| self.size.min(depth));
+        u64::from(self.value)
+            | u64::from(self.is_soft()) << 5
+            | u64::from(self.is_blackjack()) << 6
+            | pair << 7
+            | u64::from(size) << 11
+    }
+
+    // No hand holds more than 21 cards, so anything else was not packed by `packed_counts`
+    pub(crate) fn from_packed_counts(packed: u64) -> Option<Self> {
+        let mut hand = Self::new();
+        for index in 0..10 {
+            let count = (packed >> (index as u32 * RANK_BITS)) & RANK_MASK;
+            if u64::from(hand.size) + count > 21 {
+                return None;
+            }
+            for _ in 0..count {
+                hand.add_card(&Card::from_rank(Rank::from_index(index)));
+            }
         }
-        self.size = 2;
+        (hand.cards == packed).then_some(hand)
+    }
+
+    pub fn count(&self, rank: Rank) -> u8 {
+        ((self.cards >> (rank.index() as u32 * RANK_BITS)) & RANK_MASK) as u8
+    }
+
+    pub fn cards(&self) -> impl Iterator<Item = Card> {
+        let hand = *self;
+        (0..10).flat_map(move |index| {
+            let rank = Rank::from_index(index);
+            iter::repeat_n(Card::from_rank(rank), hand.count(rank) as usize)
+        })
+    }
+
+    pub fn size(&self) -> u8 {
+        self.size
+    }
+
+    pub fn is_pair(&self) -> bool {
+        self.size == 2 && self.cards().all(|card| self.count(card.rank) == 2)
     }
 
     pub fn value(&self) -> u8 {
@@ -99,30 +174,12 @@ impl Hand {
         self.soft_aces > 0
     }
 
-    pub fn hard_from_value(target: u8) -> Self {
-        Self {
-            size: 2,
-            value: target,
-            soft_aces: 0,
-            aces: 0,
-        }
-    }
-
-    pub fn soft_from_value(target: u8) -> Self {
-        Self {
-            size: 2,
-            value: target,
-            soft_aces: 1,
-            aces: if target == 12 { 2 } else { 1 },
-        }
-    }
-
-    pub fn pair_from_single_value(value: u8) -> Self {
+    pub fn pair_from_single_value(value: u8) -> Result<Self, Error> {
         let mut hand = Self::new();
-        let card = Card::from_rank(Rank::from_value(value));
+        let card = Card::from_rank(Rank::from_value(value)?);
         hand.add_card(&card);
         hand.add_card(&card);
-        hand
+        Ok(hand)
     }
 
     pub fn compare(player: &Hand, dealer: &Hand) -> Outcome {
@@ -134,14 +191,25 @@ impl Hand {
             return Outcome::Lose;
         }
 
+        Self::compare_values(player, dealer)
+    }
+
+    pub fn compare_split(player: &Hand, dealer: &Hand) -> Outcome {
+        // A two-card 21 after a split is not a natural
+        if dealer.is_blackjack() {
+            return Outcome::Lose;
+        }
+
+        Self::compare_values(player, dealer)
+    }
+
+    fn compare_values(player: &Hand, dealer: &Hand) -> Outcome {
         let player_value = player.value();
         let dealer_value = dealer.value();
 
         if player.is_bust() {
             Outcome::Lose
-        } else if dealer.is_bust() {
-            Outcome::Win
-        } else if player_value > dealer_value {
+        } else if dealer.is_bust() || player_value > dealer_value {
             Outcome::Win
         } else if player_value < dealer_value {
             Outcome::Lose
@@ -150,3 +218,85 @@ impl Hand {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hand(values: &[u8]) -> Hand {
+        let cards: Vec<_> = values
+            .iter()
+            .map(|&value| Card::from_rank(Rank::from_value(value).unwrap()))
+            .collect();
+        Hand::from_cards(&cards)
+    }
+
+    #[test]
+    fn counts_aces_as_one_when_needed() {
+        let mut soft = hand(&[11, 6]);
+        assert_eq!((soft.value(), soft.is_soft()), (17, true));
+
+        let ten = Card::from_rank(Rank::from_value(10).unwrap());
+        soft.add_card(&ten);
+        assert_eq!((soft.value(), soft.is_soft()), (17, false));
+        soft.remove_card(&ten);
+        assert_eq!((soft.value(), soft.is_soft()), (17, true));
+
+        assert_eq!(hand(&[11, 11, 11]).value(), 13);
+        // An ace drawn to a soft 21 takes both aces down to one
+        let hard = hand(&[11, 10, 11]);
+        assert_eq!((hard.value(), hard.is_soft()), (12, false));
+        assert!(hand(&[11, 10]).is_blackjack());
+        assert!(!hand(&[11, 5, 5]).is_blackjack());
+        assert!(hand(&[10, 6, 6]).is_bust());
+    }
+
+    #[test]
+    fn packed_counts_round_trip() {
+        let hands = [
+            hand(&[]),
+            hand(&[2, 2]),
+            hand(&[11, 6]),
+            hand(&[10, 9, 2]),
+            hand(&[11, 10, 11]),
+            hand(&[2, 2, 2, 2, 11, 11, 11, 11, 3, 3]),
+        ];
+        for hand in hands {
+            let unpacked = Hand::from_packed_counts(hand.packed_counts()).unwrap();
+            assert!(unpacked == hand);
+        }
+    }
+
+    #[test]
+    fn packed_totals_only_tell_apart_hands_that_play_differently() {
+        let total = |values: &[u8], max_depth| hand(values).packed_total(max_depth);
+        assert_eq!(total(&[10, 6], None), total(&[5, 5, 6], None));
+        assert_eq!(total(&[11, 5], None), total(&[2, 11, 3], None));
+        assert_ne!(total(&[11, 5], None), total(&[10, 6], None));
+        assert_ne!(total(&[8, 8], None), total(&[10, 6], None));
+        assert_ne!(total(&[11, 10], None), total(&[11, 5, 5], None));
+
+        assert_ne!(total(&[10, 6], Some(4)), total(&[5, 5, 6], Some(4)));
+        assert_eq!(
+            total(&[2, 2, 2, 2, 8], Some(4)),
+            total(&[2, 2, 2, 2, 2, 6], Some(4))
+        );
+    }
+
+    #[test]
+    fn rejects_counts_no_hand_packs() {
+        // Twenty two cards, or bits past the last rank
+        assert!(Hand::from_packed_counts(22).is_none());
+        assert!(Hand::from_packed_counts(1 << 50).is_none());
+    }
+
+    #[test]
+    fn splits_back_to_the_same_pair() {
+        let mut pair = hand(&[8, 8]);
+        assert!(pair.is_pair());
+        pair.split();
+        assert_eq!((pair.size(), pair.value()), (1, 8));
+        pair.unsplit();
+        assert!(pair == hand(&[8, 8]));
+    }
+}
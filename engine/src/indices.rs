@@ -0,0 +1,327 @@
+use std::ops::RangeInclusive;
+
+use ahash::AHashMap;
+
+use crate::{
+    card::{Card, Rank},
+    counting::CountingSystem,
+    error::Error,
+    options::GeneratorOptions,
+    rules::Rules,
+    shoe::{CountShoe, Shoe},
+    strategy::{DecisionTable, PlayerAction, RoundVariances, StrategyGenerator, StrategyTable},
+};
+
+pub struct StrategyIndex {
+    pub table: DecisionTable,
+    pub player_value: u8,
+    pub dealer_value: u8,
+    pub basic_action: PlayerAction,
+    pub action: PlayerAction,
+    // Interpolated true count past which `action` beats the basic strategy play, above it
+    // for positive counts and below it for negative ones
+    pub true_count: f64,
+    // Same crossover for the certainty equivalent, when risk aversion is enabled
+    pub risk_averse_true_count: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RiskAversion {
+    pub bankroll: f64,
+    pub bet: f64,
+}
+
+impl RiskAversion {
+    // Second order certainty equivalent of a bet under logarithmic utility, in units
+    pub fn certainty_equivalent(&self, ev: f64, variance: f64) -> f64 {
+        ev - variance * self.bet / (2.0 * self.bankroll)
+    }
+}
+
+pub struct IndexChart {
+    pub indices: Vec<StrategyIndex>,
+    // True count above which insurance is worth taking
+    pub insurance: Option<f64>,
+}
+
+struct Level {
+    true_count: i8,
+    tables: [StrategyTable; 3],
+    variances: AHashMap<(DecisionTable, u8, u8), RoundVariances>,
+    insurance_ev: f64,
+}
+
+pub struct IndexGenerator {
+    rules: Rules,
+    system: CountingSystem,
+    true_counts: RangeInclusive<i8>,
+    risk_aversion: Option<RiskAversion>,
+    options: GeneratorOptions,
+}
+
+impl IndexGenerator {
+    pub fn new(
+        rules: Rules,
+        system: CountingSystem,
+        true_counts: RangeInclusive<i8>,
+    ) -> Result<Self, Error> {
+        rules.validate()?;
+
+        Ok(Self {
+            rules,
+            system,
+            true_counts,
+            risk_aversion: None,
+            options: GeneratorOptions::default(),
+        })
+    }
+
+    pub fn with_risk_aversion(mut self, risk_aversion: RiskAversion) -> Self {
+        self.risk_aversion = Some(risk_aversion);
+        self
+    }
+
+    pub fn with_options(mut self, options: GeneratorOptions) -> Result<Self, Error> {
+        options.validate()?;
+        self.options = options;
+        Ok(self)
+    }
+
+    pub fn generate(&self) -> Result<IndexChart, Error> {
+        // Levels are sorted by true count, and the neutral shoe is always solved since every
+        // index is relative to it
+        let mut levels = Vec::new();
+        for true_count in *self.true_counts.start().min(&0)..=*self.true_counts.end().max(&0) {
+            match self.solve(true_count) {
+                Ok(level) => levels.push(level),
+                Err(Error::UnreachableTrueCount(_)) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(self.chart(&levels))
+    }
+
+    // Indices of every cell whose play changes between the solved levels
+    fn chart(&self, levels: &[Level]) -> IndexChart {
+        let neutral = levels
+            .iter()
+            .position(|level| level.true_count == 0)
+            .expect("the neutral shoe is always reachable");
+
+        let mut indices = Vec::new();
+        for (table_index, table) in DecisionTable::ALL.into_iter().enumerate() {
+            let base_table = &levels[neutral].tables[table_index];
+            for player_value in base_table.player_value_range() {
+                for dealer_value in base_table.dealer_value_range() {
+                    let value =
+                        |level: &Level| *level.tables[table_index].get(player_value, dealer_value);
+                    let basic_action = value(&levels[neutral]).action;
+
+                    for walk in [walk_up(levels, neutral), walk_down(levels, neutral)] {
+                        let Some(position) = walk
+                            .iter()
+                            .position(|level| value(level).action != basic_action)
+                        else {
+                            continue;
+                        };
+                        let action = value(walk[position]).action;
+                        let gain = |level: &Level| {
+                            let evs = value(level).evs;
+                            evs.value(action).unwrap_or(f64::NEG_INFINITY)
+                                - evs.value(basic_action).unwrap_or(f64::NEG_INFINITY)
+                        };
+
+                        let risk_averse_true_count = self.risk_aversion.and_then(|risk_aversion| {
+                            let certainty_gain = |level: &Level| {
+                                let evs = value(level).evs;
+                                let variances =
+                                    level.variances[&(table, player_value, dealer_value)];
+                                let certainty_equivalent = |action| {
+                                    evs.value(action)
+                                        .zip(variances.value(action))
+                                        .map(|(ev, variance)| {
+                                            risk_aversion.certainty_equivalent(ev, variance)
+                                        })
+                                        .unwrap_or(f64::NEG_INFINITY)
+                                };
+                                certainty_equivalent(action) - certainty_equivalent(basic_action)
+                            };
+                            first_crossover(&walk, certainty_gain)
+                        });
+
+                        indices.push(StrategyIndex {
+                            table,
+                            player_value,
+                            dealer_value,
+                            basic_action,
+                            action,
+                            true_count: crossover(walk[position - 1], walk[position], gain),
+                            risk_averse_true_count,
+                        });
+                    }
+                }
+            }
+        }
+
+        let insurance = first_crossover(&walk_up(levels, neutral), |level| level.insurance_ev);
+
+        IndexChart { indices, insurance }
+    }
+
+    fn solve(&self, true_count: i8) -> Result<Level, Error> {
+        let shoe = self
+            .system
+            .shoe_at_true_count(self.rules.num_decks, true_count)?;
+        let insurance_ev = insurance_ev(&shoe);
+        let mut strategy = StrategyGenerator::with_options(self.rules, shoe, self.options)?;
+        let tables = DecisionTable::ALL.map(|table| strategy.table(table));
+
+        let mut variances = AHashMap::new();
+        if self.risk_aversion.is_some() {
+            for (table, strategy_table) in DecisionTable::ALL.into_iter().zip(&tables) {
+                for player_value in strategy_table.player_value_range() {
+                    for dealer_value in strategy_table.dealer_value_range() {
+                        let cell_variances = strategy
+                            .cell_distributions(table, player_value, dealer_value)
+                            .variances();
+                        variances.insert((table, player_value, dealer_value), cell_variances);
+                    }
+                }
+            }
+        }
+
+        Ok(Level {
+            true_count,
+            tables,
+            variances,
+            insurance_ev,
+        })
+    }
+}
+
+// Levels from the neutral one towards the highest true count
+fn walk_up(levels: &[Level], neutral: usize) -> Vec<&Level> {
+    levels[neutral..].iter().collect()
+}
+
+// Levels from the neutral one towards the lowest true count
+fn walk_down(levels: &[Level], neutral: usize) -> Vec<&Level> {
+    levels[..=neutral].iter().rev().collect()
+}
+
+// Interpolates the true count where the gain of a deviation crosses zero, between the last
+// level without it and the first one with it
+fn crossover(previous: &Level, deviation: &Level, gain: impl Fn(&Level) -> f64) -> f64 {
+    let (gain_previous, gain_deviation) = (gain(previous), gain(deviation));
+    if gain_previous < 0.0 && gain_deviation >= 0.0 {
+        let t = gain_previous / (gain_previous - gain_deviation);
+        previous.true_count as f64 + t * (deviation.true_count - previous.true_count) as f64
+    } else {
+        deviation.true_count as f64
+    }
+}
+
+// True count where the gain first becomes positive walking away from the neutral level
+fn first_crossover(walk: &[&Level], gain: impl Fn(&Level) -> f64) -> Option<f64> {
+    let position = walk.iter().position(|level| gain(level) >= 0.0)?;
+    Some(match position {
+        0 => 0.0,
+        _ => crossover(walk[position - 1], walk[position], gain),
+    })
+}
+
+// Expected value of an insurance bet against an ace, paid 2 to 1
+fn insurance_ev(shoe: &CountShoe) -> f64 {
+    let mut shoe = shoe.clone();
+    shoe.remove_card(&Card::from_rank(Rank::Ace));
+    let ten_probability = shoe.count(Rank::Ten) as f64 / shoe.total() as f64;
+    3.0 * ten_probability - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{RoundEvs, StrategyValue};
+
+    // Level where pairs of tens against a six may stand or split with these EVs and variances
+    fn level(true_count: i8, stand: (f64, f64), split: (f64, f64)) -> Level {
+        let mut tables = DecisionTable::ALL.map(|table| {
+            let (from, to) = table.player_bounds();
+            StrategyTable::new(from, to)
+        });
+        let evs = RoundEvs {
+            hit: -1.0,
+            stand: stand.0,
+            double: -2.0,
+            surrender: None,
+            split: Some(split.0),
+        };
+        tables[2].set(10, 6, StrategyValue::from_evs(evs, 0.0));
+
+        let mut variances = AHashMap::new();
+        for (table, strategy_table) in DecisionTable::ALL.into_iter().zip(&tables) {
+            for player_value in strategy_table.player_value_range() {
+                for dealer_value in strategy_table.dealer_value_range() {
+                    let variances_of_cell = RoundVariances {
+                        hit: 1.0,
+                        stand: stand.1,
+                        double: 4.0,
+                        surrender: None,
+                        split: Some(split.1),
+                    };
+                    variances.insert((table, player_value, dealer_value), variances_of_cell);
+                }
+            }
+        }
+
+        Level {
+            true_count,
+            tables,
+            variances,
+            insurance_ev: -0.1,
+        }
+    }
+
+    #[test]
+    fn certainty_equivalent_charges_half_the_variance_per_bankroll() {
+        let risk_aversion = RiskAversion {
+            bankroll: 1000.0,
+            bet: 10.0,
+        };
+        assert_eq!(risk_aversion.certainty_equivalent(0.1, 4.0), 0.08);
+    }
+
+    #[test]
+    fn risk_averse_split_index_is_later() {
+        let generator = IndexGenerator::new(
+            Rules::default(),
+            *CountingSystem::find("hi-lo").unwrap(),
+            0..=1,
+        )
+        .unwrap()
+        .with_risk_aversion(RiskAversion {
+            bankroll: 100.0,
+            bet: 1.0,
+        });
+        let levels = [
+            level(0, (0.6, 0.8), (0.55, 3.0)),
+            level(1, (0.6, 0.8), (0.62, 3.0)),
+        ];
+        let chart = generator.chart(&levels);
+
+        let index = chart
+            .indices
+            .iter()
+            .find(|index| index.table == DecisionTable::Pair && index.player_value == 10)
+            .unwrap();
+        assert_eq!(
+            (index.dealer_value, index.basic_action, index.action),
+            (6, PlayerAction::Stand, PlayerAction::Split)
+        );
+        // The split gains -0.05 and then 0.02 units, and the extra variance of 2.2 costs
+        // 2.2 / 200 = 0.011 units more, so its certainty equivalent gains -0.061 and 0.009
+        assert!((index.true_count - 0.05 / 0.07).abs() < 1e-12);
+        assert!((index.risk_averse_true_count.unwrap() - 0.061 / 0.07).abs() < 1e-12);
+        assert_eq!(chart.insurance, None);
+    }
+}
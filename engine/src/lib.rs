@@ -1,6 +1,19 @@
+pub mod cache;
 pub mod card;
+pub mod composition;
+pub mod counting;
+pub mod dealer;
+pub mod distribution;
+pub mod error;
+pub mod exact;
 pub mod game;
 pub mod hand;
+pub mod indices;
+pub mod options;
+pub mod precomputed;
+pub mod preset;
+pub mod removal;
 pub mod rules;
+pub mod saved;
 pub mod shoe;
 pub mod strategy;
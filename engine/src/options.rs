@@ -0,0 +1,89 @@
+use crate::error::Error;
+
+// Which branches are too unlikely to be worth evaluating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub enum PruningStrategy {
+    // Probability of reaching the branch from the evaluated hand, so long sequences of small
+    // cards disappear. It is rounded up to a power of two, so a state is solved the same
+    // whichever branch reaches it first
+    Branch,
+    // Probability of the single card drawn, which keeps long sequences of likely cards
+    Draw,
+    // Every card left in the shoe is drawn
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub enum SplitMode {
+    // Both hands are drawn from the same shoe and played as if they were independent
+    Independent,
+    // The second hand is drawn from the cards the first one left, which only differs for
+    // finite shoes. Hands created by resplitting are still treated as independent
+    Sequential,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub enum Solver {
+    // Every card hit calls the solver again, using the native stack
+    Recursive,
+    // Cards hit are tracked on an explicit stack, so long hands cannot overflow small stacks
+    // such as the one of WebAssembly. The results are the same as the recursive solver
+    Iterative,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(
+    feature = "tsify",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
+pub struct GeneratorOptions {
+    pub epsilon: f64,
+    pub pruning: PruningStrategy,
+    // Most cards a player hand may hold, the player stands instead of drawing past it
+    pub max_depth: Option<u8>,
+    pub split: SplitMode,
+    pub solver: Solver,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            epsilon: 1e-5,
+            pruning: PruningStrategy::Branch,
+            max_depth: None,
+            split: SplitMode::Independent,
+            solver: if cfg!(target_arch = "wasm32") {
+                Solver::Iterative
+            } else {
+                Solver::Recursive
+            },
+        }
+    }
+}
+
+impl GeneratorOptions {
+    pub fn validate(&self) -> Result<(), Error> {
+        if !(0.0..1.0).contains(&self.epsilon) {
+            return Err(Error::InvalidEpsilon);
+        }
+        // Hitting a two card hand once must remain possible
+        if let Some(depth) = self.max_depth
+            && depth < 3
+        {
+            return Err(Error::InvalidMaxDepth(depth));
+        }
+        Ok(())
+    }
+}
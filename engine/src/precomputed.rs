@@ -0,0 +1,227 @@
+use std::io::{self, Read, Write};
+
+use crate::{
+    cache::{invalid_data, read_f64, read_u64, settings_fingerprint, write_f64, write_u64},
+    options::GeneratorOptions,
+    preset::Preset,
+    rules::Rules,
+    shoe::{InfiniteShoe, Shoe},
+    strategy::{DecisionTable, RoundEvs, StrategyGenerator, StrategyTable, StrategyValue},
+};
+
+const TABLES_MAGIC: &[u8; 8] = b"BJTABLE1";
+
+// Infinite shoe tables solved ahead of time, for the rules of every preset and the default
+// rules of the engine and the command line, with the default generator options. Builds embed
+// the bytes written by `generate` so these tables are not solved again at runtime
+pub struct PrecomputedTables {
+    entries: Vec<(u64, [StrategyTable; 3])>,
+}
+
+fn table_index(table: DecisionTable) -> usize {
+    match table {
+        DecisionTable::Hard => 0,
+        DecisionTable::Soft => 1,
+        DecisionTable::Pair => 2,
+    }
+}
+
+// The command line defaults to six splits rather than the three of `Rules::default`
+fn default_rules() -> [Rules; 2] {
+    let rules = Rules::default();
+    [
+        rules,
+        Rules {
+            max_splits: 6,
+            ..rules
+        },
+    ]
+}
+
+pub fn generate(writer: &mut impl Write) -> io::Result<()> {
+    let mut rules: Vec<_> = Preset::all().map(|preset| preset.rules).collect();
+    for default in default_rules() {
+        if !rules.contains(&default) {
+            rules.push(default);
+        }
+    }
+    let options = GeneratorOptions::default();
+
+    writer.write_all(TABLES_MAGIC)?;
+    write_u64(writer, rules.len() as u64)?;
+    for rules in rules {
+        let mut generator = StrategyGenerator::with_options(rules, InfiniteShoe::new(), options)
+            .expect("preset rules are valid");
+        write_u64(
+            writer,
+            settings_fingerprint(&rules, &options, InfiniteShoe::new().key()),
+        )?;
+        for table in DecisionTable::ALL {
+            write_table(writer, &generator.table(table))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_table(writer: &mut impl Write, table: &StrategyTable) -> io::Result<()> {
+    write_u64(writer, u64::from(table.from))?;
+    write_u64(writer, u64::from(table.to))?;
+    for value in &table.values {
+        write_f64(writer, value.evs.hit)?;
+        write_f64(writer, value.evs.stand)?;
+        write_f64(writer, value.evs.double)?;
+        for ev in [value.evs.surrender, value.evs.split] {
+            write_u64(writer, u64::from(ev.is_some()))?;
+            write_f64(writer, ev.unwrap_or(0.0))?;
+        }
+        write_f64(writer, value.error)?;
+    }
+    Ok(())
+}
+
+fn read_table(reader: &mut impl Read, table: DecisionTable) -> io::Result<StrategyTable> {
+    let bounds = (read_u64(reader)?, read_u64(reader)?);
+    let (from, to) = table.player_bounds();
+    if bounds != (u64::from(from), u64::from(to)) {
+        return Err(invalid_data("invalid table bounds"));
+    }
+
+    let mut result = StrategyTable::new(from, to);
+    for value in &mut result.values {
+        let hit = read_f64(reader)?;
+        let stand = read_f64(reader)?;
+        let double = read_f64(reader)?;
+        let mut optional = [None; 2];
+        for ev in &mut optional {
+            let present = read_u64(reader)?;
+            let amount = read_f64(reader)?;
+            *ev = match present {
+                0 => None,
+                1 => Some(amount),
+                _ => return Err(invalid_data("invalid optional EV")),
+            };
+        }
+        let [surrender, split] = optional;
+        let evs = RoundEvs {
+            hit,
+            stand,
+            double,
+            surrender,
+            split,
+        };
+        *value = StrategyValue::from_evs(evs, read_f64(reader)?);
+    }
+    Ok(result)
+}
+
+impl PrecomputedTables {
+    pub fn parse(bytes: &[u8]) -> io::Result<Self> {
+        let reader = &mut &bytes[..];
+        let mut magic = [0; TABLES_MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != TABLES_MAGIC {
+            return Err(invalid_data("not precomputed strategy tables"));
+        }
+
+        let mut entries = Vec::new();
+        for _ in 0..read_u64(reader)? {
+            let key = read_u64(reader)?;
+            let tables = [
+                read_table(reader, DecisionTable::Hard)?,
+                read_table(reader, DecisionTable::Soft)?,
+                read_table(reader, DecisionTable::Pair)?,
+            ];
+            entries.push((key, tables));
+        }
+        Ok(Self { entries })
+    }
+
+    // Table for an infinite shoe, when it was precomputed for these rules and options
+    pub fn get(
+        &self,
+        rules: &Rules,
+        options: &GeneratorOptions,
+        table: DecisionTable,
+    ) -> Option<&StrategyTable> {
+        let key = settings_fingerprint(rules, options, InfiniteShoe::new().key());
+        let (_, tables) = self.entries.iter().find(|(other, _)| *other == key)?;
+        Some(&tables[table_index(table)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::{Solver, SplitMode};
+
+    fn generated() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        generate(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn parses_generated_tables() {
+        let tables = PrecomputedTables::parse(&generated()).unwrap();
+        let rules = Rules::default();
+        let options = GeneratorOptions::default();
+        let mut generator = StrategyGenerator::with_options(rules, InfiniteShoe::new(), options)
+            .expect("default rules are valid");
+        for table in DecisionTable::ALL {
+            assert_eq!(
+                tables.get(&rules, &options, table),
+                Some(&generator.table(table))
+            );
+        }
+    }
+
+    #[test]
+    fn matches_only_the_same_settings() {
+        let tables = PrecomputedTables::parse(&generated()).unwrap();
+        let rules = Rules::default();
+        let options = GeneratorOptions::default();
+        for rules in Preset::all()
+            .map(|preset| preset.rules)
+            .chain(default_rules())
+        {
+            assert!(tables.get(&rules, &options, DecisionTable::Hard).is_some());
+        }
+
+        // Either solver gives the same tables
+        for solver in [Solver::Recursive, Solver::Iterative] {
+            let options = GeneratorOptions { solver, ..options };
+            assert!(tables.get(&rules, &options, DecisionTable::Pair).is_some());
+        }
+        let other_options = [
+            GeneratorOptions {
+                epsilon: 1e-4,
+                ..options
+            },
+            GeneratorOptions {
+                split: SplitMode::Sequential,
+                ..options
+            },
+            GeneratorOptions {
+                max_depth: Some(5),
+                ..options
+            },
+        ];
+        for options in other_options {
+            assert!(tables.get(&rules, &options, DecisionTable::Soft).is_none());
+        }
+        let rules = Rules {
+            max_splits: 2,
+            ..rules
+        };
+        assert!(tables.get(&rules, &options, DecisionTable::Soft).is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_bytes() {
+        let bytes = generated();
+        assert!(PrecomputedTables::parse(&bytes[..bytes.len() - 1]).is_err());
+        assert!(PrecomputedTables::parse(b"BJCACHE2").is_err());
+        assert!(PrecomputedTables::parse(&[]).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use std::str::FromStr;
+
+use crate::{
+    error::Error,
+    rules::{BlackjackPayout, Rules, Soft17Rule, SurrenderType},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub rules: Rules,
+}
+
+const PRESETS: [Preset; 4] = [
+    Preset {
+        name: "vegas-strip-6d",
+        description: "Las Vegas Strip six-deck shoe game",
+        rules: Rules {
+            blackjack_payout: BlackjackPayout::Ratio3to2,
+            num_decks: 6,
+            dealer_soft_17: Soft17Rule::Stand,
+            double_after_split_allowed: true,
+            surrender: SurrenderType::Late,
+            max_splits: 3,
+            dealer_peek: true,
+        },
+    },
+    Preset {
+        name: "atlantic-city",
+        description: "Atlantic City eight-deck shoe game",
+        rules: Rules {
+            blackjack_payout: BlackjackPayout::Ratio3to2,
+            num_decks: 8,
+            dealer_soft_17: Soft17Rule::Stand,
+            double_after_split_allowed: true,
+            surrender: SurrenderType::Late,
+            max_splits: 3,
+            dealer_peek: true,
+        },
+    },
+    Preset {
+        name: "downtown-single-deck",
+        description: "Downtown Las Vegas hand-pitched single-deck game",
+        rules: Rules {
+            blackjack_payout: BlackjackPayout::Ratio3to2,
+            num_decks: 1,
+            dealer_soft_17: Soft17Rule::Hit,
+            double_after_split_allowed: false,
+            surrender: SurrenderType::None,
+            max_splits: 1,
+            dealer_peek: true,
+        },
+    },
+    Preset {
+        name: "european-nhc",
+        description: "European no hole card game, doubles and splits are lost to a dealer natural",
+        rules: Rules {
+            blackjack_payout: BlackjackPayout::Ratio3to2,
+            num_decks: 6,
+            dealer_soft_17: Soft17Rule::Stand,
+            double_after_split_allowed: true,
+            surrender: SurrenderType::None,
+            max_splits: 1,
+            dealer_peek: false,
+        },
+    },
+];
+
+impl Preset {
+    pub fn all() -> impl Iterator<Item = &'static Preset> {
+        PRESETS.iter()
+    }
+
+    pub fn find(name: &str) -> Option<&'static Preset> {
+        PRESETS.iter().find(|preset| preset.name == name)
+    }
+}
+
+impl FromStr for Preset {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Preset::find(name).copied().ok_or(Error::UnknownPreset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_are_valid() {
+        for preset in Preset::all() {
+            assert_eq!(preset.rules.validate(), Ok(()), "{}", preset.name);
+        }
+    }
+
+    #[test]
+    fn presets_round_trip_by_name() {
+        for preset in Preset::all() {
+            assert_eq!(preset.name.parse::<Preset>(), Ok(*preset));
+        }
+        assert_eq!("vegas-strip".parse::<Preset>(), Err(Error::UnknownPreset));
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{
+    card::{Card, Rank},
+    error::Error,
+    hand::Hand,
+    options::GeneratorOptions,
+    rules::Rules,
+    shoe::{CountShoe, HandType, Shoe},
+    strategy::{DecisionTable, RoundEvs, StrategyGenerator, StrategyTable},
+};
+
+pub struct DecisionEffects {
+    pub table: DecisionTable,
+    pub player_value: u8,
+    pub dealer_value: u8,
+    // Probability of facing the decision with a freshly dealt hand
+    pub probability: f64,
+    // Gain of the best play over the runner-up with a full shoe
+    pub gain: f64,
+    // Change in the gain of the best play over the runner-up per rank removed
+    pub effects: [f64; 10],
+}
+
+pub struct RemovalEffects {
+    // Cards in the full shoe the effects were computed from
+    pub cards: usize,
+    pub base_ev: f64,
+    // Change in the overall expected return per rank removed, indexed like ranks
+    pub effects: [f64; 10],
+    pub decisions: Vec<DecisionEffects>,
+}
+
+struct Solution {
+    ev: f64,
+    tables: [StrategyTable; 3],
+}
+
+impl RemovalEffects {
+    pub fn compute(rules: Rules, options: GeneratorOptions) -> Result<Self, Error> {
+        let shoe = CountShoe::new(rules.num_decks)?;
+        let base = solve(rules, shoe.clone(), options)?;
+
+        let mut solutions = Vec::with_capacity(10);
+        for index in 0..10 {
+            let mut removed = shoe.clone();
+            removed.remove_card(&Card::from_rank(Rank::from_index(index)));
+            solutions.push(solve(rules, removed, options)?);
+        }
+
+        let effects = std::array::from_fn(|index| solutions[index].ev - base.ev);
+
+        let mut decisions = Vec::new();
+        for (table_index, table) in base.tables.iter().enumerate() {
+            for player_value in table.player_value_range() {
+                for dealer_value in table.dealer_value_range() {
+                    let base_evs = &table.get(player_value, dealer_value).evs;
+                    let Some((best, runner_up)) = top_two(base_evs) else {
+                        continue;
+                    };
+                    let gain = |evs: &RoundEvs| {
+                        let evs = action_evs(evs);
+                        evs[best].unwrap_or(0.0) - evs[runner_up].unwrap_or(0.0)
+                    };
+                    let base_gain = gain(base_evs);
+
+                    decisions.push(DecisionEffects {
+                        table: DecisionTable::ALL[table_index],
+                        player_value,
+                        dealer_value,
+                        probability: decision_probability(
+                            &shoe,
+                            DecisionTable::ALL[table_index],
+                            player_value,
+                            dealer_value,
+                        ),
+                        gain: base_gain,
+                        effects: std::array::from_fn(|index| {
+                            let evs = &solutions[index].tables[table_index]
+                                .get(player_value, dealer_value)
+                                .evs;
+                            gain(evs) - base_gain
+                        }),
+                    });
+                }
+            }
+        }
+
+        Ok(Self {
+            cards: shoe.total(),
+            base_ev: base.ev,
+            effects,
+            decisions,
+        })
+    }
+}
+
+fn solve(rules: Rules, shoe: CountShoe, options: GeneratorOptions) -> Result<Solution, Error> {
+    let mut strategy = StrategyGenerator::with_options(rules, shoe, options)?;
+    Ok(Solution {
+        ev: strategy.expected_return(),
+        tables: DecisionTable::ALL.map(|table| strategy.table(table)),
+    })
+}
+
+fn action_evs(evs: &RoundEvs) -> [Option<f64>; 5] {
+    [
+        Some(evs.hit),
+        Some(evs.stand),
+        Some(evs.double),
+        evs.surrender,
+        evs.split,
+    ]
+}
+
+fn top_two(evs: &RoundEvs) -> Option<(usize, usize)> {
+    let mut ranked: Vec<_> = action_evs(evs)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, ev)| ev.map(|ev| (index, ev)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    match ranked.as_slice() {
+        [(best, _), (runner_up, _), ..] => Some((*best, *runner_up)),
+        _ => None,
+    }
+}
+
+fn decision_probability(
+    shoe: &CountShoe,
+    table: DecisionTable,
+    player_value: u8,
+    dealer_value: u8,
+) -> f64 {
+    let dealer_upcard =
+        Card::from_rank(Rank::from_value(dealer_value).expect("dealer values are card values"));
+    let upcard_probability = shoe.count(dealer_upcard.rank) as f64 / shoe.total() as f64;
+
+    let mut shoe = shoe.clone();
+    shoe.remove_card(&dealer_upcard);
+    // Pairs are only counted in the pair table
+    let hand_probability = match table {
+        DecisionTable::Hard | DecisionTable::Soft => {
+            let hand_type = if table == DecisionTable::Hard {
+                HandType::Hard
+            } else {
+                HandType::Soft
+            };
+            shoe.iter_player_hands(player_value, hand_type)
+                .filter(|(hand, _)| !hand.is_pair())
+                .map(|(_, weight)| weight)
+                .sum()
+        }
+        DecisionTable::Pair => {
+            let hand =
+                Hand::pair_from_single_value(player_value).expect("pair values are card values");
+            shoe.composition_weight(&hand)
+        }
+    };
+
+    upcard_probability * hand_probability
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::OnceLock;
+
+    use super::*;
+
+    // Effects are small next to the noise pruning adds, so the shoe is solved more precisely
+    // than the other tests do. It takes a while, so the tests share a single result
+    pub(crate) fn one_deck_effects() -> &'static RemovalEffects {
+        static EFFECTS: OnceLock<RemovalEffects> = OnceLock::new();
+        EFFECTS.get_or_init(|| {
+            let rules = Rules {
+                num_decks: 1,
+                ..Rules::default()
+            };
+            let options = GeneratorOptions {
+                epsilon: 1e-4,
+                ..GeneratorOptions::default()
+            };
+            RemovalEffects::compute(rules, options).unwrap()
+        })
+    }
+
+    #[test]
+    fn small_cards_help_the_player_and_big_ones_hurt() {
+        let effects = one_deck_effects();
+        let effect = |rank: Rank| effects.effects[rank.index()];
+        for rank in [Rank::Two, Rank::Three, Rank::Four, Rank::Five, Rank::Six] {
+            assert!(effect(rank) > 0.0, "{rank:?}");
+        }
+        assert!(effect(Rank::Ten) < 0.0);
+        assert!(effect(Rank::Ace) < 0.0);
+        // The five is the card the player least wants left in the shoe
+        assert!(
+            effects
+                .effects
+                .iter()
+                .all(|effect| *effect <= effects.effects[Rank::Five.index()])
+        );
+
+        // Dealing an unseen card leaves the expected return as it was, so the effects weighted
+        // by the number of cards of each rank average to about zero
+        let total: f64 = effects
+            .effects
+            .iter()
+            .enumerate()
+            .map(|(index, effect)| {
+                let cards = if Rank::from_index(index) == Rank::Ten {
+                    16.0
+                } else {
+                    4.0
+                };
+                effect * cards
+            })
+            .sum();
+        assert!((total / 52.0).abs() < 1e-3, "{total}");
+    }
+}
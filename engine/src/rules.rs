@@ -1,18 +1,50 @@
-use crate::hand::Hand;
+use crate::{
+    card::Card,
+    error::Error,
+    game::SPLITS_BITS,
+    hand::Hand,
+    shoe::{RANK_KEY_BITS, TEN_KEY_BITS},
+};
+
+// Solver cache keys pack the cards left of each rank and the number of splits in a few bits,
+// these are the most that fit
+pub const MAX_DECKS: u8 = max_decks();
+pub const MAX_SPLITS: u8 = (1 << SPLITS_BITS) - 1;
+
+const fn max_decks() -> u8 {
+    let ranks = ((1 << RANK_KEY_BITS) - 1) / 4;
+    let tens = ((1 << TEN_KEY_BITS) - 1) / 16;
+    (if ranks < tens { ranks } else { tens }) as u8
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
 pub enum BlackjackPayout {
     Ratio3to2,
     Ratio6to5,
 }
 
+impl BlackjackPayout {
+    pub fn ratio(&self) -> f64 {
+        match self {
+            BlackjackPayout::Ratio3to2 => 1.5,
+            BlackjackPayout::Ratio6to5 => 1.2,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
 pub enum Soft17Rule {
     Hit,
     Stand,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
 pub enum SurrenderType {
     None,
     Early,
@@ -20,6 +52,16 @@ pub enum SurrenderType {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(
+    feature = "tsify",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
 pub struct Rules {
     pub blackjack_payout: BlackjackPayout,
     pub num_decks: u8,
@@ -27,6 +69,14 @@ pub struct Rules {
     pub double_after_split_allowed: bool,
     pub surrender: SurrenderType,
     pub max_splits: u8,
+    #[cfg_attr(feature = "serde", serde(default = "default_dealer_peek"))]
+    #[cfg_attr(feature = "tsify", tsify(optional))]
+    pub dealer_peek: bool,
+}
+
+#[cfg(feature = "serde")]
+fn default_dealer_peek() -> bool {
+    true
 }
 
 impl Default for Rules {
@@ -38,11 +88,26 @@ impl Default for Rules {
             double_after_split_allowed: false,
             surrender: SurrenderType::None,
             max_splits: 3,
+            dealer_peek: true,
         }
     }
 }
 
 impl Rules {
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.num_decks == 0 || self.num_decks > MAX_DECKS {
+            return Err(Error::InvalidDeckCount(self.num_decks));
+        }
+        if self.max_splits == 0 || self.max_splits > MAX_SPLITS {
+            return Err(Error::InvalidMaxSplits(self.max_splits));
+        }
+        Ok(())
+    }
+
+    pub fn dealer_checks_blackjack(&self, upcard: &Card) -> bool {
+        self.dealer_peek && upcard.rank.value() >= 10
+    }
+
     pub fn dealer_must_stand(&self, hand: &Hand) -> bool {
         let value = hand.value();
         if value > 17 {
@@ -61,3 +126,37 @@ impl Rules {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_decks_and_splits() {
+        assert_eq!(Rules::default().validate(), Ok(()));
+        let rules = |num_decks, max_splits| Rules {
+            num_decks,
+            max_splits,
+            ..Rules::default()
+        };
+        assert_eq!(rules(1, 1).validate(), Ok(()));
+        assert_eq!(rules(MAX_DECKS, MAX_SPLITS).validate(), Ok(()));
+
+        assert_eq!(rules(0, 3).validate(), Err(Error::InvalidDeckCount(0)));
+        assert_eq!(
+            rules(MAX_DECKS + 1, 3).validate(),
+            Err(Error::InvalidDeckCount(MAX_DECKS + 1))
+        );
+        assert_eq!(rules(6, 0).validate(), Err(Error::InvalidMaxSplits(0)));
+        assert_eq!(
+            rules(6, MAX_SPLITS + 1).validate(),
+            Err(Error::InvalidMaxSplits(MAX_SPLITS + 1))
+        );
+    }
+
+    // The rules form of the app repeats these limits
+    #[test]
+    fn limits_match_the_app_form() {
+        assert_eq!((MAX_DECKS, MAX_SPLITS), (15, 7));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    error::Error,
+    options::GeneratorOptions,
+    rules::Rules,
+    strategy::{DecisionTable, StrategyTable},
+};
+
+// Bumped whenever a saved document could no longer be read the same way
+pub const SAVED_TABLES_VERSION: u32 = 1;
+
+// Strategy tables along with the settings they were solved with, as written by the CLI. In
+// JSON the fields are camelCase:
+//
+// - `version`: `SAVED_TABLES_VERSION` when the document was written
+// - `rules` and `options`: the `Rules` and `GeneratorOptions` the tables were solved with
+// - `infiniteShoe`: true when solved for an infinite shoe rather than `rules.numDecks` decks
+// - `hard`, `soft` and `pair`: each table that was solved, left out otherwise. A table holds
+//   `from` and `to`, its lowest and highest player values, and `values`, its cells row by row
+//   with dealer values 2 to 11 in each row. A cell holds the best `action`, its `ev`, the
+//   `error` bound of pruning, and `evs` with the `hit`, `stand`, `double`, `surrender` and
+//   `split` EVs, the last two null when the action is not available
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct SavedTables {
+    pub version: u32,
+    pub rules: Rules,
+    pub options: GeneratorOptions,
+    pub infinite_shoe: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub hard: Option<StrategyTable>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub soft: Option<StrategyTable>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub pair: Option<StrategyTable>,
+}
+
+impl SavedTables {
+    pub fn new(rules: Rules, options: GeneratorOptions, infinite_shoe: bool) -> Self {
+        Self {
+            version: SAVED_TABLES_VERSION,
+            rules,
+            options,
+            infinite_shoe,
+            hard: None,
+            soft: None,
+            pair: None,
+        }
+    }
+
+    fn slot(&mut self, table: DecisionTable) -> &mut Option<StrategyTable> {
+        match table {
+            DecisionTable::Hard => &mut self.hard,
+            DecisionTable::Soft => &mut self.soft,
+            DecisionTable::Pair => &mut self.pair,
+        }
+    }
+
+    pub fn get(&self, table: DecisionTable) -> Option<&StrategyTable> {
+        match table {
+            DecisionTable::Hard => self.hard.as_ref(),
+            DecisionTable::Soft => self.soft.as_ref(),
+            DecisionTable::Pair => self.pair.as_ref(),
+        }
+    }
+
+    pub fn set(&mut self, table: DecisionTable, value: StrategyTable) {
+        *self.slot(table) = Some(value);
+    }
+
+    pub fn take(&mut self, table: DecisionTable) -> Option<StrategyTable> {
+        self.slot(table).take()
+    }
+
+    // Checks a loaded document before its tables are indexed
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.version != SAVED_TABLES_VERSION {
+            return Err(Error::UnsupportedTablesVersion(self.version));
+        }
+        self.rules.validate()?;
+        self.options.validate()?;
+
+        for table in DecisionTable::ALL {
+            if let Some(saved) = self.get(table) {
+                let (from, to) = table.player_bounds();
+                let size = usize::from(to - from + 1) * 10;
+                if (saved.from, saved.to) != (from, to) || saved.values.len() != size {
+                    return Err(Error::InvalidSavedTable(table));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved() -> SavedTables {
+        let mut saved = SavedTables::new(Rules::default(), GeneratorOptions::default(), true);
+        for table in [DecisionTable::Hard, DecisionTable::Pair] {
+            let (from, to) = table.player_bounds();
+            saved.set(table, StrategyTable::new(from, to));
+        }
+        saved
+    }
+
+    #[test]
+    fn holds_the_tables_set() {
+        let mut saved = saved();
+        assert!(saved.get(DecisionTable::Hard).is_some());
+        assert!(saved.get(DecisionTable::Soft).is_none());
+        assert!(saved.take(DecisionTable::Pair).is_some());
+        assert!(saved.get(DecisionTable::Pair).is_none());
+        assert_eq!(saved.validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut saved = saved();
+        saved.version = SAVED_TABLES_VERSION + 1;
+        assert_eq!(
+            saved.validate(),
+            Err(Error::UnsupportedTablesVersion(SAVED_TABLES_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        let mut saved = saved();
+        saved.rules.num_decks = 0;
+        assert_eq!(saved.validate(), Err(Error::InvalidDeckCount(0)));
+
+        let mut saved = self::saved();
+        saved.options.epsilon = 2.0;
+        assert_eq!(saved.validate(), Err(Error::InvalidEpsilon));
+    }
+
+    #[test]
+    fn rejects_tables_of_the_wrong_size() {
+        let mut saved = saved();
+        let (from, to) = DecisionTable::Soft.player_bounds();
+        saved.set(DecisionTable::Soft, StrategyTable::new(from + 1, to));
+        assert_eq!(
+            saved.validate(),
+            Err(Error::InvalidSavedTable(DecisionTable::Soft))
+        );
+
+        let mut saved = self::saved();
+        if let Some(hard) = &mut saved.hard {
+            hard.values.pop();
+        }
+        assert_eq!(
+            saved.validate(),
+            Err(Error::InvalidSavedTable(DecisionTable::Hard))
+        );
+    }
+}
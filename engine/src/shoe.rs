@@ -1,16 +1,30 @@
-use std::iter;
+use std::ops::RangeInclusive;
 
 use crate::{
     card::{Card, Rank},
+    error::Error,
     hand::Hand,
+    rules::MAX_DECKS,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HandType {
     Hard,
     Soft,
 }
 
 pub trait Shoe {
+    // Whether drawing changes the odds of the next draw, otherwise hands with the same total
+    // play the same whatever cards they hold
+    const REMOVES_CARDS: bool;
+
+    // Identifies the remaining cards among the shoes a generator reaches, it is what caches
+    // store in place of the shoe
+    fn key(&self) -> u64;
+
+    // Whether drawing from this shoe can leave the shoe identified by `key`
+    fn can_reach(&self, key: u64) -> bool;
+
     fn get_draws(&self) -> Vec<(Card, f64)>;
 
     fn iter_player_hands(
@@ -19,11 +33,66 @@ pub trait Shoe {
         hand_type: HandType,
     ) -> impl Iterator<Item = (Hand, f64)>;
 
+    fn composition_weight(&self, hand: &Hand) -> f64;
+
     fn remove_card(&mut self, card: &Card);
 
     fn add_card(&mut self, _card: &Card);
 }
 
+// Lists every composition of `sizes` cards reaching the given total, along with the
+// probability of being dealt it from `shoe`
+pub fn iter_compositions<S: Shoe>(
+    shoe: &S,
+    player_value: u8,
+    hand_type: HandType,
+    sizes: RangeInclusive<u8>,
+) -> impl Iterator<Item = (Hand, f64)> {
+    let mut vec = Vec::new();
+    push_compositions(
+        shoe,
+        &mut vec,
+        Hand::new(),
+        0,
+        &sizes,
+        player_value,
+        hand_type,
+    );
+    vec.into_iter()
+}
+
+fn push_compositions<S: Shoe>(
+    shoe: &S,
+    vec: &mut Vec<(Hand, f64)>,
+    hand: Hand,
+    from_index: usize,
+    sizes: &RangeInclusive<u8>,
+    player_value: u8,
+    hand_type: HandType,
+) {
+    if sizes.contains(&hand.size()) {
+        let is_soft = hand_type == HandType::Soft;
+        if hand.value() == player_value && hand.is_soft() == is_soft {
+            let weight = shoe.composition_weight(&hand);
+            if weight > 0.0 {
+                vec.push((hand, weight));
+            }
+        }
+    }
+    if hand.size() >= *sizes.end() {
+        return;
+    }
+
+    // Ranks are added in index order so each composition is visited once
+    for index in from_index..10 {
+        let mut next_hand = hand;
+        next_hand.add_card(&Card::from_rank(Rank::from_index(index)));
+        if !next_hand.is_bust() {
+            push_compositions(shoe, vec, next_hand, index, sizes, player_value, hand_type);
+        }
+    }
+}
+
 const CARD_PROBABILITIES: [(Rank, f64); 10] = [
     (Rank::Two, 1.0 / 13.0),
     (Rank::Three, 1.0 / 13.0),
@@ -41,6 +110,16 @@ const CARD_PROBABILITIES: [(Rank, f64); 10] = [
 pub struct InfiniteShoe;
 
 impl Shoe for InfiniteShoe {
+    const REMOVES_CARDS: bool = false;
+
+    fn key(&self) -> u64 {
+        0
+    }
+
+    fn can_reach(&self, _key: u64) -> bool {
+        true
+    }
+
     fn get_draws(&self) -> Vec<(Card, f64)> {
         let mut vec = Vec::with_capacity(CARD_PROBABILITIES.len());
         for (rank, weight) in CARD_PROBABILITIES {
@@ -52,14 +131,37 @@ impl Shoe for InfiniteShoe {
     fn iter_player_hands(
         &self,
         player_value: u8,
-        soft: HandType,
+        hand_type: HandType,
     ) -> impl Iterator<Item = (Hand, f64)> {
-        let hand = match soft {
-            HandType::Hard => Hand::hard_from_value(player_value),
-            HandType::Soft => Hand::soft_from_value(player_value),
+        // Card removal has no effect, so a single representative composition is enough
+        let ranks = match hand_type {
+            HandType::Soft if player_value == 12 => vec![11, 11],
+            HandType::Soft => vec![11, player_value - 11],
+            HandType::Hard if player_value == 21 => vec![2, 9, 10],
+            HandType::Hard if player_value >= 12 => vec![player_value - 10, 10],
+            HandType::Hard if player_value == 4 => vec![2, 2],
+            HandType::Hard => vec![2, player_value - 2],
         };
 
-        iter::once((hand, 1.0))
+        let mut hand = Hand::new();
+        for value in ranks {
+            let rank = Rank::from_value(value).expect("player totals split into card values");
+            hand.add_card(&Card::from_rank(rank));
+        }
+
+        Some((hand, 1.0)).into_iter()
+    }
+
+    fn composition_weight(&self, hand: &Hand) -> f64 {
+        let mut weight = 1.0;
+        let mut drawn = 0;
+        for (rank, probability) in CARD_PROBABILITIES {
+            for k in 0..hand.count(rank) {
+                drawn += 1;
+                weight *= probability * drawn as f64 / (k + 1) as f64;
+            }
+        }
+        weight
     }
 
     fn remove_card(&mut self, _card: &Card) {}
@@ -67,6 +169,12 @@ impl Shoe for InfiniteShoe {
     fn add_card(&mut self, _card: &Card) {}
 }
 
+impl Default for InfiniteShoe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl InfiniteShoe {
     pub fn new() -> Self {
         Self {}
@@ -75,16 +183,38 @@ impl InfiniteShoe {
 
 #[derive(Clone, Hash, PartialEq, Eq)]
 pub struct CountShoe {
+    decks: u8,
     counts: [usize; 10],
     total: usize,
 }
 
 impl Shoe for CountShoe {
+    const REMOVES_CARDS: bool = true;
+
+    // Counts take 6 bits and tens 8, which is what limits shoes to `MAX_DECKS` decks. The
+    // deck count is the same for every shoe a generator reaches
+    fn key(&self) -> u64 {
+        self.counts.iter().enumerate().fold(0, |key, (i, &count)| {
+            let bits = key_bits(i);
+            debug_assert!(count < 1 << bits);
+            key << bits | count as u64
+        })
+    }
+
+    fn can_reach(&self, mut key: u64) -> bool {
+        (0..10).rev().all(|i| {
+            let bits = key_bits(i);
+            let count = (key & ((1 << bits) - 1)) as usize;
+            key >>= bits;
+            count <= self.counts[i]
+        })
+    }
+
     fn get_draws(&self) -> Vec<(Card, f64)> {
         let mut vec = Vec::with_capacity(10);
         let total = self.total as f64;
         for i in 0..10 {
-            let card = Card::from_rank(Rank::from_value((i as u8) + 2));
+            let card = Card::from_rank(Rank::from_index(i));
             vec.push((card, self.counts[i] as f64 / total));
         }
         vec
@@ -92,16 +222,45 @@ impl Shoe for CountShoe {
 
     fn iter_player_hands(
         &self,
-        _player_value: u8,
-        _hand_type: HandType,
+        player_value: u8,
+        hand_type: HandType,
     ) -> impl Iterator<Item = (Hand, f64)> {
-        let vec = Vec::new();
+        // Use the fewest cards that can reach the total, which is two for everything but
+        // hard 21
+        let mut vec = Vec::new();
+        let mut size = 2;
+        while vec.is_empty() && size <= 21 {
+            vec.extend(iter_compositions(
+                self,
+                player_value,
+                hand_type,
+                size..=size,
+            ));
+            size += 1;
+        }
 
         vec.into_iter()
     }
 
+    // Probability of being dealt exactly these cards, in any order
+    fn composition_weight(&self, hand: &Hand) -> f64 {
+        let mut weight = 1.0;
+        let mut drawn = 0;
+        for i in 0..10 {
+            let count = hand.count(Rank::from_index(i)) as usize;
+            for k in 0..count {
+                drawn += 1;
+                weight *= self.counts[i].saturating_sub(k) as f64 * drawn as f64
+                    / ((k + 1) * (self.total + 1 - drawn)) as f64;
+            }
+        }
+        weight
+    }
+
+    // Removing a missing card does nothing, so `add_card` would then leave an extra one
     fn remove_card(&mut self, card: &Card) {
-        let i = (card.rank.value() - 2) as usize;
+        let i = card.rank.index();
+        debug_assert!(self.counts[i] > 0, "no {:?} left in the shoe", card.rank);
         if self.counts[i] > 0 {
             self.counts[i] -= 1;
             self.total -= 1;
@@ -109,24 +268,84 @@ impl Shoe for CountShoe {
     }
 
     fn add_card(&mut self, card: &Card) {
-        let i = (card.rank.value() - 2) as usize;
+        let i = card.rank.index();
         self.counts[i] += 1;
         self.total += 1;
     }
 }
 
-impl CountShoe {
-    pub fn new(decks: u8) -> Self {
-        let mut counts = [0; 10];
+// Bits of a shoe key holding the count of each rank left, tens need more
+pub(crate) const RANK_KEY_BITS: u32 = 6;
+pub(crate) const TEN_KEY_BITS: u32 = 8;
 
-        for i in 0..10 {
-            counts[i] = (decks as usize) * 4;
+fn key_bits(index: usize) -> u32 {
+    if index == 8 {
+        TEN_KEY_BITS
+    } else {
+        RANK_KEY_BITS
+    }
+}
+
+impl CountShoe {
+    pub fn new(decks: u8) -> Result<Self, Error> {
+        if decks == 0 || decks > MAX_DECKS {
+            return Err(Error::InvalidDeckCount(decks));
         }
+
+        let mut counts = [(decks as usize) * 4; 10];
         counts[8] = (decks as usize) * 16;
 
-        Self {
+        Ok(Self {
+            decks,
             total: decks as usize * 52,
             counts,
+        })
+    }
+
+    pub fn decks(&self) -> u8 {
+        self.decks
+    }
+
+    pub fn count(&self, rank: Rank) -> usize {
+        self.counts[rank.index()]
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn dealt(&self) -> usize {
+        self.decks as usize * 52 - self.total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_fit_the_largest_shoe() {
+        let mut shoe = CountShoe::new(MAX_DECKS).unwrap();
+        let full = shoe.key();
+        for value in [2, 10, 11] {
+            let card = Card::from_rank(Rank::from_value(value).unwrap());
+            shoe.remove_card(&card);
+            assert_ne!(shoe.key(), full);
+            assert!(CountShoe::new(MAX_DECKS).unwrap().can_reach(shoe.key()));
+            assert!(!shoe.can_reach(full));
+            shoe.add_card(&card);
         }
+        assert_eq!(shoe.key(), full);
+    }
+
+    #[test]
+    fn rejects_deck_counts_outside_the_limits() {
+        assert_eq!(CountShoe::new(0).err(), Some(Error::InvalidDeckCount(0)));
+        assert_eq!(
+            CountShoe::new(MAX_DECKS + 1).err(),
+            Some(Error::InvalidDeckCount(MAX_DECKS + 1))
+        );
+        let shoe = CountShoe::new(1).unwrap();
+        assert_eq!((shoe.decks(), shoe.dealt()), (1, 0));
     }
 }
@@ -1,16 +1,33 @@
-use std::{hash::Hash, ops::RangeInclusive};
+use std::{
+    hash::Hash,
+    io::{self, Read, Write},
+    ops::{ControlFlow, RangeInclusive},
+};
 
 use ahash::AHashMap;
 
 use crate::{
+    cache::{
+        Cache, CacheStats, HeapSize, invalid_data, read_f64, read_u64, settings_fingerprint,
+        write_f64, write_u64,
+    },
     card::{Card, Rank},
-    game::GameState,
+    distribution::{OutcomeDistribution, OutcomesByDealer, RoundDistributions},
+    error::Error,
+    game::{GameState, StateKey},
     hand::{Hand, Outcome},
-    rules::{BlackjackPayout, Rules, SurrenderType},
-    shoe::Shoe,
+    options::{GeneratorOptions, PruningStrategy, Solver, SplitMode},
+    rules::{Rules, SurrenderType},
+    shoe::{HandType, Shoe},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "tsify",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
 pub enum PlayerAction {
     Hit,
     Stand,
@@ -20,7 +37,13 @@ pub enum PlayerAction {
     Surrender,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "tsify",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
 pub struct RoundEvs {
     pub hit: f64,
     pub stand: f64,
@@ -30,6 +53,30 @@ pub struct RoundEvs {
 }
 
 impl RoundEvs {
+    pub fn weighted_average(evs: &[(RoundEvs, f64)]) -> Self {
+        let total_weight: f64 = evs.iter().map(|(_, weight)| weight).sum();
+        let average = |ev: fn(&RoundEvs) -> f64| {
+            evs.iter()
+                .map(|(evs, weight)| ev(evs) * weight)
+                .sum::<f64>()
+                / total_weight
+        };
+        let average_option = |ev: fn(&RoundEvs) -> Option<f64>| {
+            evs.iter()
+                .map(|(evs, weight)| ev(evs).map(|ev| ev * weight))
+                .sum::<Option<f64>>()
+                .map(|ev| ev / total_weight)
+        };
+
+        Self {
+            hit: average(|evs| evs.hit),
+            stand: average(|evs| evs.stand),
+            double: average(|evs| evs.double),
+            surrender: average_option(|evs| evs.surrender),
+            split: average_option(|evs| evs.split),
+        }
+    }
+
     pub fn best(&self) -> (PlayerAction, f64) {
         let mut best_action = PlayerAction::Stand;
         let mut best_value = self.stand;
@@ -48,381 +95,1804 @@ impl RoundEvs {
             }
         }
 
-        if let Some(split_val) = self.split {
-            if split_val > best_value {
-                best_value = split_val;
-                best_action = PlayerAction::Split;
-            }
+        if let Some(split_val) = self.split
+            && split_val > best_value
+        {
+            best_value = split_val;
+            best_action = PlayerAction::Split;
         }
 
-        if let Some(surrender_val) = self.surrender {
-            if surrender_val > best_value {
-                best_value = surrender_val;
-                best_action = PlayerAction::Surrender;
-            }
+        if let Some(surrender_val) = self.surrender
+            && surrender_val > best_value
+        {
+            best_value = surrender_val;
+            best_action = PlayerAction::Surrender;
         }
 
         (best_action, best_value)
     }
+
+    pub fn value(&self, action: PlayerAction) -> Option<f64> {
+        match action {
+            PlayerAction::Hit => Some(self.hit),
+            PlayerAction::Stand => Some(self.stand),
+            PlayerAction::DoubleOrHit | PlayerAction::DoubleOrStand => Some(self.double),
+            PlayerAction::Split => self.split,
+            PlayerAction::Surrender => self.surrender,
+        }
+    }
+
+    // Once a card has been drawn, only hitting or standing remain
+    pub fn best_after_hit(&self) -> (PlayerAction, f64) {
+        if self.hit > self.stand {
+            (PlayerAction::Hit, self.hit)
+        } else {
+            (PlayerAction::Stand, self.stand)
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct DealerHandKey<S: Shoe> {
-    upcard: Card,
-    shoe: S,
+#[derive(Clone, Copy)]
+pub struct RoundVariances {
+    pub hit: f64,
+    pub stand: f64,
+    pub double: f64,
+    pub surrender: Option<f64>,
+    pub split: Option<f64>,
+}
+
+impl RoundVariances {
+    pub fn value(&self, action: PlayerAction) -> Option<f64> {
+        match action {
+            PlayerAction::Hit => Some(self.hit),
+            PlayerAction::Stand => Some(self.stand),
+            PlayerAction::DoubleOrHit | PlayerAction::DoubleOrStand => Some(self.double),
+            PlayerAction::Split => self.split,
+            PlayerAction::Surrender => self.surrender,
+        }
+    }
+}
+
+// EV of an action along with a bound on how far pruning may have moved it from the exact value
+#[derive(Clone, Copy, Default)]
+struct Estimate {
+    ev: f64,
+    error: f64,
+}
+
+impl Estimate {
+    fn max(self, other: Estimate) -> Estimate {
+        Estimate {
+            ev: self.ev.max(other.ev),
+            error: self.error.max(other.error),
+        }
+    }
+
+    fn scaled(self, factor: f64) -> Estimate {
+        Estimate {
+            ev: self.ev * factor,
+            error: self.error * factor,
+        }
+    }
+
+    fn add(&mut self, other: Estimate, weight: f64) {
+        self.ev += other.ev * weight;
+        self.error += other.error * weight;
+    }
+
+    // Dropping part of the probability mass and renormalizing moves an average by at most
+    // the dropped share times its distance to the furthest outcome in `range`
+    fn normalize(self, total_weight: f64, pruned_weight: f64, range: (f64, f64)) -> Estimate {
+        if total_weight <= 0.0 {
+            // Nothing was evaluated, the worst outcome is never preferred and the error spans
+            // the whole range
+            return Estimate {
+                ev: range.0,
+                error: range.1 - range.0,
+            };
+        }
+        let ev = self.ev / total_weight;
+        let pruned_share = pruned_weight / (total_weight + pruned_weight);
+        Estimate {
+            ev,
+            error: self.error / total_weight + pruned_share * pruning_spread(ev, range),
+        }
+    }
+}
+
+// Shoes are kept in the order they are first reached, so sums over them are reproducible
+fn record_shoe<S: Shoe + Clone + Eq>(shoes: &mut Vec<(S, f64)>, shoe: &S, probability: f64) {
+    match shoes.iter_mut().find(|(other, _)| other == shoe) {
+        Some((_, total)) => *total += probability,
+        None => shoes.push((shoe.clone(), probability)),
+    }
+}
+
+impl HeapSize for Estimate {}
+
+// Hand of the iterative solver being hit, along with the draws evaluated so far
+struct HitFrame {
+    key: StateKey,
+    branch_weight: f64,
+    draws: Vec<(Card, f64)>,
+    next: usize,
+    total: Estimate,
+    total_weight: f64,
+    pruned_weight: f64,
+}
+
+impl HitFrame {
+    fn new(key: StateKey, branch_weight: f64, draws: Vec<(Card, f64)>) -> Self {
+        Self {
+            key,
+            branch_weight,
+            draws,
+            next: 0,
+            total: Estimate::default(),
+            total_weight: 0.0,
+            pruned_weight: 0.0,
+        }
+    }
+}
+
+// Hand of the iterative solver whose shoes after hitting are collected, `card` is the one
+// drawn to reach it and taken back once all of its draws are done
+struct HitShoesFrame {
+    card: Option<Card>,
+    branch_weight: f64,
+    probability: f64,
+    draws: Vec<(Card, f64)>,
+    next: usize,
+}
+
+fn pruning_spread(ev: f64, (min, max): (f64, f64)) -> f64 {
+    f64::max(ev - min, max - ev)
+}
+
+const CACHE_MAGIC: &[u8; 8] = b"BJCACHE2";
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct DealerHandKey {
+    upcard: usize,
+    shoe: u64,
+    peeked: bool,
 }
 
 pub struct StrategyGenerator<S: Shoe> {
     rules: Rules,
     shoe: S,
-    hit_cache: AHashMap<GameState<S>, f64>,
-    stand_cache: AHashMap<GameState<S>, f64>,
-    double_cache: AHashMap<GameState<S>, f64>,
-    split_cache: AHashMap<GameState<S>, f64>,
-    dealer_hand_cache: AHashMap<DealerHandKey<S>, Vec<(Hand, f64)>>,
-    epsilon: f64,
+    hit_cache: Cache<StateKey, Estimate>,
+    stand_cache: Cache<StateKey, Estimate>,
+    double_cache: Cache<StateKey, Estimate>,
+    split_cache: Cache<StateKey, Estimate>,
+    dealer_hand_cache: Cache<DealerHandKey, Vec<(Hand, f64)>>,
+    // Outcome distribution of each action, later decisions are those with the best EV
+    distribution_cache: Cache<(StateKey, PlayerAction), OutcomesByDealer>,
+    options: GeneratorOptions,
+    // Bytes the caches may hold, unbounded by default
+    cache_limit: Option<usize>,
+    // Bytes of the entries used since the caches were last aged, summed as they grow
+    recent_bytes: usize,
 }
 
 impl<S: Shoe + Clone + Eq + Hash> StrategyGenerator<S> {
-    pub fn new(rules: Rules, shoe: S) -> Self {
-        Self {
+    pub fn new(rules: Rules, shoe: S) -> Result<Self, Error> {
+        Self::with_options(rules, shoe, GeneratorOptions::default())
+    }
+
+    pub fn with_options(rules: Rules, shoe: S, options: GeneratorOptions) -> Result<Self, Error> {
+        rules.validate()?;
+        options.validate()?;
+
+        Ok(Self {
             rules,
             shoe,
-            hit_cache: AHashMap::new(),
-            stand_cache: AHashMap::new(),
-            double_cache: AHashMap::new(),
-            split_cache: AHashMap::new(),
-            dealer_hand_cache: AHashMap::new(),
-            epsilon: 1e-5,
+            hit_cache: Cache::new(),
+            stand_cache: Cache::new(),
+            double_cache: Cache::new(),
+            split_cache: Cache::new(),
+            dealer_hand_cache: Cache::new(),
+            distribution_cache: Cache::new(),
+            options,
+            cache_limit: None,
+            recent_bytes: 0,
+        })
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn shoe(&self) -> &S {
+        &self.shoe
+    }
+
+    pub fn options(&self) -> &GeneratorOptions {
+        &self.options
+    }
+
+    // Dealing a card only makes the shoes holding it unreachable, cached states of every other
+    // shoe remain valid and later solves reuse them. Branch pruned states are keyed by their
+    // weight as for cache limits, so the ones kept hold the values a new generator would solve
+    pub fn remove_card(&mut self, card: &Card) -> Result<(), Error> {
+        let available = self
+            .shoe
+            .get_draws()
+            .into_iter()
+            .any(|(other, weight)| other.rank.index() == card.rank.index() && weight > 0.0);
+        if !available {
+            return Err(Error::CardNotInShoe(card.rank.value()));
+        }
+        self.shoe.remove_card(card);
+
+        let shoe = &self.shoe;
+        self.hit_cache.retain(|key| shoe.can_reach(key.shoe));
+        self.stand_cache.retain(|key| shoe.can_reach(key.shoe));
+        self.double_cache.retain(|key| shoe.can_reach(key.shoe));
+        self.split_cache.retain(|key| shoe.can_reach(key.shoe));
+        self.dealer_hand_cache
+            .retain(|key| shoe.can_reach(key.shoe));
+        self.distribution_cache
+            .retain(|(key, _)| shoe.can_reach(key.shoe));
+        self.recount_caches();
+        Ok(())
+    }
+
+    // Once the limit is reached, entries that were not used for a while are dropped. States
+    // solved again give the values that were dropped, so the limit only changes how long
+    // solving takes
+    pub fn set_cache_limit(&mut self, bytes: Option<usize>) {
+        self.cache_limit = bytes;
+        self.recount_caches();
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        let mut stats = self.hit_cache.stats();
+        stats += self.stand_cache.stats();
+        stats += self.double_cache.stats();
+        stats += self.split_cache.stats();
+        stats += self.dealer_hand_cache.stats();
+        stats += self.distribution_cache.stats();
+        stats
+    }
+
+    // Identifies the settings that cached values depend on, saved caches are only loaded by
+    // generators sharing them
+    pub fn cache_fingerprint(&self) -> u64 {
+        settings_fingerprint(&self.rules, &self.options, self.shoe.key())
+    }
+
+    // Only EVs and the dealer hands behind them are saved, distributions are solved again
+    pub fn save_caches(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(CACHE_MAGIC)?;
+        write_u64(writer, self.cache_fingerprint())?;
+
+        for cache in [
+            &self.hit_cache,
+            &self.stand_cache,
+            &self.double_cache,
+            &self.split_cache,
+        ] {
+            write_u64(writer, cache.stats().entries as u64)?;
+            for (key, estimate) in cache.iter() {
+                write_u64(writer, key.round)?;
+                write_u64(writer, key.shoe)?;
+                write_f64(writer, estimate.ev)?;
+                write_f64(writer, estimate.error)?;
+            }
+        }
+
+        write_u64(writer, self.dealer_hand_cache.stats().entries as u64)?;
+        for (key, hands) in self.dealer_hand_cache.iter() {
+            write_u64(writer, key.upcard as u64)?;
+            write_u64(writer, key.shoe)?;
+            write_u64(writer, u64::from(key.peeked))?;
+            write_u64(writer, hands.len() as u64)?;
+            for (hand, weight) in hands {
+                write_u64(writer, hand.packed_counts())?;
+                write_f64(writer, *weight)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Adds the entries saved by `save_caches`, or returns false without reading them when they
+    // were saved with other settings
+    pub fn load_caches(&mut self, reader: &mut impl Read) -> io::Result<bool> {
+        let mut magic = [0; CACHE_MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != CACHE_MAGIC {
+            return Err(invalid_data("not a saved solver cache"));
+        }
+        if read_u64(reader)? != self.cache_fingerprint() {
+            return Ok(false);
+        }
+
+        for cache in [
+            &mut self.hit_cache,
+            &mut self.stand_cache,
+            &mut self.double_cache,
+            &mut self.split_cache,
+        ] {
+            for _ in 0..read_u64(reader)? {
+                let key = StateKey {
+                    round: read_u64(reader)?,
+                    shoe: read_u64(reader)?,
+                };
+                let estimate = Estimate {
+                    ev: read_f64(reader)?,
+                    error: read_f64(reader)?,
+                };
+                cache.insert(key, estimate);
+            }
+        }
+
+        for _ in 0..read_u64(reader)? {
+            let upcard = read_u64(reader)?;
+            let shoe = read_u64(reader)?;
+            let peeked = match read_u64(reader)? {
+                0 => false,
+                1 => true,
+                _ => return Err(invalid_data("invalid dealer peek")),
+            };
+            if upcard >= 10 {
+                return Err(invalid_data("invalid dealer upcard"));
+            }
+            let len = read_u64(reader)?;
+            // Dealer hands are grouped by total, a longer list can only be corrupt
+            if len > 32 {
+                return Err(invalid_data("invalid dealer hand count"));
+            }
+            let mut hands = Vec::with_capacity(len as usize);
+            for _ in 0..len {
+                let hand = Hand::from_packed_counts(read_u64(reader)?)
+                    .ok_or_else(|| invalid_data("invalid dealer hand"))?;
+                hands.push((hand, read_f64(reader)?));
+            }
+            let key = DealerHandKey {
+                upcard: upcard as usize,
+                shoe,
+                peeked,
+            };
+            self.dealer_hand_cache.insert(key, hands);
+        }
+
+        self.recount_caches();
+        Ok(true)
+    }
+
+    // Entries used since the caches were last aged may fill half the limit, older ones are
+    // dropped when they do. Called with what an insert grew the caches by
+    fn limit_caches(&mut self, grown: usize) {
+        self.recent_bytes += grown;
+        let Some(limit) = self.cache_limit else {
+            return;
+        };
+        if self.recent_bytes > limit / 2 {
+            self.hit_cache.age();
+            self.stand_cache.age();
+            self.double_cache.age();
+            self.split_cache.age();
+            self.dealer_hand_cache.age();
+            self.distribution_cache.age();
+            self.recent_bytes = 0;
         }
     }
 
-    pub fn get_dealer_hands(&mut self, state: &mut GameState<S>) -> Vec<(Hand, f64)> {
+    // Sums the recent entries again after the caches changed other than by inserting in them
+    fn recount_caches(&mut self) {
+        self.recent_bytes = self.hit_cache.recent_bytes()
+            + self.stand_cache.recent_bytes()
+            + self.double_cache.recent_bytes()
+            + self.split_cache.recent_bytes()
+            + self.dealer_hand_cache.recent_bytes()
+            + self.distribution_cache.recent_bytes();
+        self.limit_caches(0);
+    }
+
+    // Ranks missing from the shoe are skipped whatever the strategy, even with an epsilon of 0,
+    // since the shoe cannot give back a card it never removed
+    fn is_pruned(&self, branch_weight: f64, draw_weight: f64) -> bool {
+        draw_weight <= 0.0
+            || match self.options.pruning {
+                PruningStrategy::Branch => branch_weight * draw_weight < self.options.epsilon,
+                PruningStrategy::Draw => draw_weight < self.options.epsilon,
+                PruningStrategy::None => false,
+            }
+    }
+
+    // Whether drawing again is worth evaluating, past the maximum depth the player stands
+    fn can_hit(&self, hand: &Hand) -> bool {
+        hand.value() < 21
+            && self
+                .options
+                .max_depth
+                .is_none_or(|depth| hand.size() < depth)
+    }
+
+    pub fn get_dealer_hands(&mut self, state: &GameState<S>) -> Vec<(Hand, f64)> {
         let key = DealerHandKey {
-            upcard: state.dealer_upcard,
-            shoe: state.shoe.clone(),
+            upcard: state.dealer_upcard.rank.index(),
+            shoe: state.shoe.key(),
+            peeked: state.dealer_peeked,
         };
         if let Some(cached) = self.dealer_hand_cache.get(&key) {
             return cached.clone();
         }
 
-        let mut map = AHashMap::<Hand, f64>::with_capacity(200);
+        // Final dealer hands only matter through their total and whether they are a natural
+        let mut map = AHashMap::<(u8, bool), (Hand, f64)>::with_capacity(16);
 
+        // The dealer draws from the shoe the player left, without removing what it drew
+        let draws = state.shoe.get_draws();
         let mut stack = Vec::with_capacity(100);
         let mut start_hand = Hand::new();
         start_hand.add_card(&state.dealer_upcard);
-        stack.push((start_hand, 1.0));
+        if state.dealer_peeked {
+            // The dealer checked for blackjack, so the hole card cannot complete a natural
+            let mut total_weight = 0.0;
+            for &(card, draw_weight) in &draws {
+                let mut hand = start_hand;
+                hand.add_card(&card);
+                if !hand.is_blackjack() {
+                    total_weight += draw_weight;
+                    stack.push((hand, draw_weight));
+                }
+            }
+            for (_, weight) in stack.iter_mut() {
+                *weight /= total_weight;
+            }
+            stack.retain(|(_, weight)| !self.is_pruned(1.0, *weight));
+        } else {
+            stack.push((start_hand, 1.0));
+        }
 
         while let Some((hand, weight)) = stack.pop() {
-            if weight < self.epsilon {
-                continue;
-            }
             if self.rules.dealer_must_stand(&hand) {
-                map.entry(hand)
-                    .and_modify(|w| *w += weight)
-                    .or_insert(weight);
+                map.entry((hand.value(), hand.is_blackjack()))
+                    .and_modify(|(_, w)| *w += weight)
+                    .or_insert((hand, weight));
                 continue;
             }
 
-            for (card, draw_weight) in state.shoe.get_draws() {
-                state.shoe.remove_card(&card);
-
-                let mut next_hand = hand.clone();
+            for &(card, draw_weight) in &draws {
+                if self.is_pruned(weight, draw_weight) {
+                    continue;
+                }
+                let mut next_hand = hand;
                 next_hand.add_card(&card);
                 stack.push((next_hand, weight * draw_weight));
-
-                state.shoe.add_card(&card);
             }
         }
 
-        let result: Vec<_> = map.into_iter().collect();
+        // Sorted so that sums over the hands do not depend on the order of the map
+        let mut result: Vec<_> = map.into_values().collect();
+        result.sort_unstable_by_key(|(hand, _)| (hand.value(), hand.is_blackjack()));
 
-        self.dealer_hand_cache.insert(key, result.clone());
+        let grown = self.dealer_hand_cache.insert(key, result.clone());
+
+        self.limit_caches(grown);
 
         result
     }
 
     pub fn expected_value_stand(&mut self, state: &mut GameState<S>, branch_weight: f64) -> f64 {
-        if let Some(item) = self.stand_cache.get(state) {
+        self.estimate_stand(state, branch_weight).ev
+    }
+
+    fn estimate_stand(&mut self, state: &mut GameState<S>, branch_weight: f64) -> Estimate {
+        let (key, branch_weight) = state.key(&self.options, branch_weight);
+        if let Some(item) = self.stand_cache.get(&key) {
             return *item;
         }
         let mut total_ev = 0.0;
         let mut total_weight = 0.0;
 
         for (dealer_hand, hand_weight) in self.get_dealer_hands(state) {
-            let weight = branch_weight * hand_weight;
-            if weight < self.epsilon {
+            if self.is_pruned(branch_weight, hand_weight) {
                 continue;
             }
-            let ev = match Hand::compare(&state.player_hand, &dealer_hand) {
-                Outcome::Win => {
-                    if state.player_hand.is_blackjack() {
-                        match self.rules.blackjack_payout {
-                            BlackjackPayout::Ratio3to2 => 1.5,
-                            BlackjackPayout::Ratio6to5 => 1.2,
-                        }
-                    } else {
-                        1.0
-                    }
-                }
-                Outcome::Push => 0.0,
-                Outcome::Lose => -1.0,
-            };
+            let ev = self.stand_outcome(state, &dealer_hand);
             total_weight += hand_weight;
             total_ev += ev * hand_weight;
         }
 
-        let ev = total_ev / total_weight;
-        self.stand_cache.insert(state.clone(), ev);
-        ev
+        // Dealer hands pruned here or while drawing them are what is missing from a total of one
+        let range = (-1.0, self.rules.blackjack_payout.ratio());
+        let estimate = Estimate {
+            ev: total_ev,
+            error: 0.0,
+        }
+        .normalize(total_weight, (1.0 - total_weight).max(0.0), range);
+        let grown = self.stand_cache.insert(key, estimate);
+        self.limit_caches(grown);
+        estimate
+    }
+
+    fn stand_outcome(&self, state: &GameState<S>, dealer_hand: &Hand) -> f64 {
+        let outcome = if state.splits > 0 {
+            Hand::compare_split(&state.player_hand, dealer_hand)
+        } else {
+            Hand::compare(&state.player_hand, dealer_hand)
+        };
+        match outcome {
+            Outcome::Win => {
+                if state.splits == 0 && state.player_hand.is_blackjack() {
+                    self.rules.blackjack_payout.ratio()
+                } else {
+                    1.0
+                }
+            }
+            Outcome::Push => 0.0,
+            Outcome::Lose => -1.0,
+        }
     }
 
     pub fn expected_value_hit(&mut self, state: &mut GameState<S>, branch_weight: f64) -> f64 {
-        if let Some(item) = self.hit_cache.get(&state) {
+        self.estimate_hit(state, branch_weight).ev
+    }
+
+    fn estimate_hit(&mut self, state: &mut GameState<S>, branch_weight: f64) -> Estimate {
+        match self.options.solver {
+            Solver::Recursive => self.estimate_hit_recursive(state, branch_weight),
+            Solver::Iterative => self.estimate_hit_iterative(state, branch_weight),
+        }
+    }
+
+    fn estimate_hit_recursive(&mut self, state: &mut GameState<S>, branch_weight: f64) -> Estimate {
+        let (key, branch_weight) = state.key(&self.options, branch_weight);
+        if let Some(item) = self.hit_cache.get(&key) {
             return *item;
         }
-        let mut total_ev = 0.0;
+        let mut total = Estimate::default();
         let mut total_weight = 0.0;
+        let mut pruned_weight = 0.0;
 
         for (card, draw_weight) in state.shoe.get_draws() {
             let weight = branch_weight * draw_weight;
-            if weight < self.epsilon {
+            if self.is_pruned(branch_weight, draw_weight) {
+                pruned_weight += draw_weight;
                 continue;
             }
             state.player_hand.add_card(&card);
-            let ev = if state.player_hand.is_bust() {
-                -1.0
+            let estimate = if state.player_hand.is_bust() {
+                Estimate {
+                    ev: -1.0,
+                    error: 0.0,
+                }
             } else {
                 state.shoe.remove_card(&card);
-                let ev = if state.player_hand.value() == 21 {
-                    self.expected_value_stand(state, weight)
+                let estimate = if !self.can_hit(&state.player_hand) {
+                    self.estimate_stand(state, weight)
                 } else {
-                    let ev_hit = self.expected_value_hit(state, weight);
-                    let ev_stand = self.expected_value_stand(state, weight);
-                    f64::max(ev_hit, ev_stand)
+                    let hit = self.estimate_hit(state, weight);
+                    let stand = self.estimate_stand(state, weight);
+                    hit.max(stand)
                 };
                 state.shoe.add_card(&card);
-                ev
+                estimate
             };
             state.player_hand.remove_card(&card);
 
-            total_ev += ev * draw_weight;
+            total.add(estimate, draw_weight);
             total_weight += draw_weight;
         }
 
-        let ev = total_ev / total_weight;
-        self.hit_cache.insert(state.clone(), ev);
-        ev
+        // Outcomes after a hit range from losing to winning the bet
+        let estimate = total.normalize(total_weight, pruned_weight, (-1.0, 1.0));
+        let grown = self.hit_cache.insert(key, estimate);
+        self.limit_caches(grown);
+        estimate
+    }
+
+    // Same evaluation as the recursive solver, in the same order so the caches and results
+    // match, with the hands still being hit kept on `frames`
+    fn estimate_hit_iterative(&mut self, state: &mut GameState<S>, branch_weight: f64) -> Estimate {
+        let (key, branch_weight) = state.key(&self.options, branch_weight);
+        if let Some(item) = self.hit_cache.get(&key) {
+            return *item;
+        }
+        let mut frames = vec![HitFrame::new(key, branch_weight, state.shoe.get_draws())];
+        // Estimate of hitting the hand reached by the last draw of the top frame
+        let mut hit: Option<Estimate> = None;
+
+        'solve: loop {
+            let frame = frames.last_mut().unwrap();
+            if let Some(hit) = hit.take() {
+                let (card, draw_weight) = frame.draws[frame.next - 1];
+                let stand = self.estimate_stand(state, frame.branch_weight * draw_weight);
+                state.shoe.add_card(&card);
+                state.player_hand.remove_card(&card);
+
+                frame.total.add(hit.max(stand), draw_weight);
+                frame.total_weight += draw_weight;
+            }
+
+            while let Some(&(card, draw_weight)) = frame.draws.get(frame.next) {
+                frame.next += 1;
+                let weight = frame.branch_weight * draw_weight;
+                if self.is_pruned(frame.branch_weight, draw_weight) {
+                    frame.pruned_weight += draw_weight;
+                    continue;
+                }
+                state.player_hand.add_card(&card);
+                let estimate = if state.player_hand.is_bust() {
+                    Estimate {
+                        ev: -1.0,
+                        error: 0.0,
+                    }
+                } else {
+                    state.shoe.remove_card(&card);
+                    if self.can_hit(&state.player_hand) {
+                        // The card stays drawn until the estimate of hitting again is known
+                        let (key, weight) = state.key(&self.options, weight);
+                        match self.hit_cache.get(&key) {
+                            Some(item) => hit = Some(*item),
+                            None => frames.push(HitFrame::new(key, weight, state.shoe.get_draws())),
+                        }
+                        continue 'solve;
+                    }
+                    let estimate = self.estimate_stand(state, weight);
+                    state.shoe.add_card(&card);
+                    estimate
+                };
+                state.player_hand.remove_card(&card);
+
+                frame.total.add(estimate, draw_weight);
+                frame.total_weight += draw_weight;
+            }
+
+            let frame = frames.pop().unwrap();
+            let estimate =
+                frame
+                    .total
+                    .normalize(frame.total_weight, frame.pruned_weight, (-1.0, 1.0));
+            let grown = self.hit_cache.insert(frame.key, estimate);
+            self.limit_caches(grown);
+            if frames.is_empty() {
+                return estimate;
+            }
+            hit = Some(estimate);
+        }
     }
 
     pub fn expected_value_double(&mut self, state: &mut GameState<S>, branch_weight: f64) -> f64 {
-        if let Some(item) = self.double_cache.get(state) {
+        self.estimate_double(state, branch_weight).ev
+    }
+
+    fn estimate_double(&mut self, state: &mut GameState<S>, branch_weight: f64) -> Estimate {
+        let (key, branch_weight) = state.key(&self.options, branch_weight);
+        if let Some(item) = self.double_cache.get(&key) {
             return *item;
         }
 
-        let mut total_ev = 0.0;
+        let mut total = Estimate::default();
         let mut total_weight = 0.0;
+        let mut pruned_weight = 0.0;
 
         for (card, draw_weight) in state.shoe.get_draws() {
             let weight = branch_weight * draw_weight;
-            if weight < self.epsilon {
+            if self.is_pruned(branch_weight, draw_weight) {
+                pruned_weight += draw_weight;
                 continue;
             }
             state.player_hand.add_card(&card);
             state.shoe.remove_card(&card);
-            let ev = self.expected_value_stand(state, weight) * 2.0;
+            let estimate = self.estimate_stand(state, weight).scaled(2.0);
             state.shoe.add_card(&card);
             state.player_hand.remove_card(&card);
 
-            total_ev += draw_weight * ev;
+            total.add(estimate, draw_weight);
             total_weight += draw_weight;
         }
 
-        let ev = total_ev / total_weight;
-        self.double_cache.insert(state.clone(), ev);
-        ev
+        let estimate = total.normalize(total_weight, pruned_weight, (-2.0, 2.0));
+        let grown = self.double_cache.insert(key, estimate);
+        self.limit_caches(grown);
+        estimate
     }
 
     pub fn expected_value_split(&mut self, state: &mut GameState<S>, branch_weight: f64) -> f64 {
-        if let Some(item) = self.split_cache.get(state) {
+        self.estimate_split(state, branch_weight).ev
+    }
+
+    fn estimate_split(&mut self, state: &mut GameState<S>, branch_weight: f64) -> Estimate {
+        let (key, branch_weight) = state.key(&self.options, branch_weight);
+        if let Some(item) = self.split_cache.get(&key) {
             return *item;
         }
 
-        let mut total_ev = 0.0;
+        let first = self.estimate_split_hand(state, branch_weight);
+        // Hands created by resplitting are always treated as independent
+        let second = match self.options.split {
+            SplitMode::Sequential if state.splits == 0 => {
+                self.estimate_second_split_hand(state, branch_weight)
+            }
+            _ => first,
+        };
+        let estimate = Estimate {
+            ev: first.ev + second.ev,
+            error: first.error + second.error,
+        };
+        let grown = self.split_cache.insert(key, estimate);
+        self.limit_caches(grown);
+        estimate
+    }
+
+    // Average value of one hand after splitting the pair of `state`
+    fn estimate_split_hand(&mut self, state: &mut GameState<S>, branch_weight: f64) -> Estimate {
+        let mut total = Estimate::default();
         let mut total_weight = 0.0;
+        let mut pruned_weight = 0.0;
 
         state.split();
+        let range = self.split_hand_range(state);
         for (card, draw_weight) in state.shoe.get_draws() {
             let weight = branch_weight * draw_weight;
-            if weight < self.epsilon {
+            if self.is_pruned(branch_weight, draw_weight) {
+                pruned_weight += draw_weight;
                 continue;
             }
             let initial_value = state.player_hand.value();
             state.player_hand.add_card(&card);
             state.shoe.remove_card(&card);
-            let mut ev = f64::max(
-                self.expected_value_hit(state, weight),
-                self.expected_value_stand(state, weight),
-            );
-            if self.rules.double_after_split_allowed {
-                ev = ev.max(self.expected_value_double(state, weight))
-            }
-            if card.rank.value() == initial_value && state.splits < self.rules.max_splits {
-                ev = ev.max(self.expected_value_split(state, weight))
-            }
+            let (_, estimate) = self.split_hand_action(state, &card, initial_value, weight);
             state.shoe.add_card(&card);
             state.player_hand.remove_card(&card);
 
-            total_ev += draw_weight * ev * 2.0;
+            total.add(estimate, draw_weight);
             total_weight += draw_weight;
         }
         state.unsplit();
 
-        let ev = total_ev / total_weight;
-        self.split_cache.insert(state.clone(), ev);
-        ev
+        total.normalize(total_weight, pruned_weight, range)
     }
 
-    pub fn expected_value_surrender(&mut self, state: &mut GameState<S>) -> f64 {
-        match self.rules.surrender {
-            SurrenderType::Early => -0.5,
-            SurrenderType::Late => {
-                let mut hand = Hand::new();
-                hand.add_card(&state.dealer_upcard);
-
-                let mut total_ev = 0.0;
-                let mut total_weight = 0.0;
-
-                for (card, draw_weight) in state.shoe.get_draws() {
-                    hand.add_card(&card);
-
-                    let ev = if hand.is_blackjack() { -1.0 } else { -0.5 };
-                    total_ev += draw_weight * ev;
-                    total_weight += draw_weight;
-
-                    hand.remove_card(&card);
-                }
-
-                total_ev / total_weight
-            }
-            SurrenderType::None => unreachable!(),
-        }
+    // A hand is worth at least standing, and at most a doubled bet which each further split
+    // can double again
+    fn split_hand_range(&self, state: &GameState<S>) -> (f64, f64) {
+        (
+            -1.0,
+            2f64.powi(i32::from(self.rules.max_splits - state.splits) + 1),
+        )
     }
 
-    pub fn eval_round(
+    // Best play of a split hand once its second card is drawn
+    fn split_hand_action(
         &mut self,
-        player_hand: Hand,
-        dealer_upcard: Card,
-        is_pair: bool,
-    ) -> RoundEvs {
-        let mut state = GameState {
-            dealer_upcard,
-            player_hand,
-            shoe: self.shoe.clone(),
-            splits: 0,
-        };
-
-        state.shoe.remove_card(&dealer_upcard);
-
-        let evs = RoundEvs {
-            hit: self.expected_value_hit(&mut state, 1.0),
-            stand: self.expected_value_stand(&mut state, 1.0),
-            double: self.expected_value_double(&mut state, 1.0),
-            surrender: if self.rules.surrender != SurrenderType::None {
-                Some(self.expected_value_surrender(&mut state))
-            } else {
-                None
-            },
-            split: if is_pair {
-                Some(self.expected_value_split(&mut state, 1.0))
-            } else {
-                None
-            },
+        state: &mut GameState<S>,
+        card: &Card,
+        initial_value: u8,
+        branch_weight: f64,
+    ) -> (PlayerAction, Estimate) {
+        let hit = self.estimate_hit(state, branch_weight);
+        let stand = self.estimate_stand(state, branch_weight);
+        let mut action = if hit.ev > stand.ev {
+            PlayerAction::Hit
+        } else {
+            PlayerAction::Stand
         };
-
-        state.shoe.add_card(&dealer_upcard);
-
-        evs
-    }
-
-    pub fn hard_table(&mut self) -> StrategyTable {
-        let mut table = StrategyTable::new(5, 21);
-
-        for player_value in table.player_value_range() {
-            for dealer_value in table.dealer_value_range() {
-                let dealer_upcard = Card {
-                    rank: Rank::from_value(dealer_value),
-                };
-
-                let player_hand = Hand::hard_from_value(player_value);
-
-                let evs = self.eval_round(player_hand, dealer_upcard, false);
-                let value = StrategyValue::from_evs(evs);
-
-                table.set(player_value, dealer_value, value);
+        let mut estimate = hit.max(stand);
+        if self.rules.double_after_split_allowed {
+            let double = self.estimate_double(state, branch_weight);
+            if double.ev > estimate.ev {
+                action = PlayerAction::DoubleOrHit;
             }
+            estimate = estimate.max(double);
         }
-
-        table
+        if card.rank.value() == initial_value && state.splits < self.rules.max_splits {
+            let split = self.estimate_split(state, branch_weight);
+            if split.ev > estimate.ev {
+                action = PlayerAction::Split;
+            }
+            estimate = estimate.max(split);
+        }
+        (action, estimate)
     }
 
-    pub fn soft_table(&mut self) -> StrategyTable {
-        let mut table = StrategyTable::new(13, 21);
-
-        for player_value in table.player_value_range() {
-            for dealer_value in table.dealer_value_range() {
-                let dealer_upcard = Card {
-                    rank: Rank::from_value(dealer_value),
-                };
-
-                let player_hand = Hand::soft_from_value(player_value);
-
-                let evs = self.eval_round(player_hand, dealer_upcard, false);
-                let value = StrategyValue::from_evs(evs);
+    // Average value of the second hand, drawn from each shoe the first one can leave
+    fn estimate_second_split_hand(
+        &mut self,
+        state: &mut GameState<S>,
+        branch_weight: f64,
+    ) -> Estimate {
+        let mut shoes = Vec::new();
+        self.collect_split_hand_shoes(state, branch_weight, &mut shoes);
 
-                table.set(player_value, dealer_value, value);
+        let mut total = Estimate::default();
+        let mut total_weight = 0.0;
+        let mut second = state.clone();
+        for (shoe, probability) in shoes {
+            if self.is_pruned(branch_weight, probability) {
+                continue;
             }
+            // Played as precisely as the first hand, rarer shoes would prune every draw
+            second.shoe = shoe;
+            let estimate = self.estimate_split_hand(&mut second, branch_weight);
+            total.add(estimate, probability);
+            total_weight += probability;
         }
 
-        table
+        state.split();
+        let range = self.split_hand_range(state);
+        state.unsplit();
+        // Whatever the first hand pruned is missing from a total of one
+        total.normalize(total_weight, (1.0 - total_weight).max(0.0), range)
+    }
+
+    fn collect_split_hand_shoes(
+        &mut self,
+        state: &mut GameState<S>,
+        branch_weight: f64,
+        shoes: &mut Vec<(S, f64)>,
+    ) {
+        state.split();
+        for (card, draw_weight) in state.shoe.get_draws() {
+            let weight = branch_weight * draw_weight;
+            if self.is_pruned(branch_weight, draw_weight) {
+                continue;
+            }
+            let initial_value = state.player_hand.value();
+            state.player_hand.add_card(&card);
+            state.shoe.remove_card(&card);
+            match self
+                .split_hand_action(state, &card, initial_value, weight)
+                .0
+            {
+                PlayerAction::Hit => self.collect_hit_shoes(state, weight, draw_weight, shoes),
+                PlayerAction::DoubleOrHit => {
+                    for (card, next_weight) in state.shoe.get_draws() {
+                        if self.is_pruned(weight, next_weight) {
+                            continue;
+                        }
+                        state.shoe.remove_card(&card);
+                        record_shoe(shoes, &state.shoe, draw_weight * next_weight);
+                        state.shoe.add_card(&card);
+                    }
+                }
+                // Cards drawn by hands created by resplitting are not followed
+                _ => record_shoe(shoes, &state.shoe, draw_weight),
+            }
+            state.shoe.add_card(&card);
+            state.player_hand.remove_card(&card);
+        }
+        state.unsplit();
+    }
+
+    // Follows a hand that hits until it stands or busts, along with the probability of each
+    // shoe it leaves
+    fn collect_hit_shoes(
+        &mut self,
+        state: &mut GameState<S>,
+        branch_weight: f64,
+        probability: f64,
+        shoes: &mut Vec<(S, f64)>,
+    ) {
+        match self.options.solver {
+            Solver::Recursive => {
+                self.collect_hit_shoes_recursive(state, branch_weight, probability, shoes)
+            }
+            Solver::Iterative => {
+                self.collect_hit_shoes_iterative(state, branch_weight, probability, shoes)
+            }
+        }
+    }
+
+    fn collect_hit_shoes_recursive(
+        &mut self,
+        state: &mut GameState<S>,
+        branch_weight: f64,
+        probability: f64,
+        shoes: &mut Vec<(S, f64)>,
+    ) {
+        for (card, draw_weight) in state.shoe.get_draws() {
+            let weight = branch_weight * draw_weight;
+            if self.is_pruned(branch_weight, draw_weight) {
+                continue;
+            }
+            state.player_hand.add_card(&card);
+            state.shoe.remove_card(&card);
+            if !state.player_hand.is_bust()
+                && self.can_hit(&state.player_hand)
+                && self.expected_value_hit(state, weight) > self.expected_value_stand(state, weight)
+            {
+                self.collect_hit_shoes_recursive(state, weight, probability * draw_weight, shoes);
+            } else {
+                record_shoe(shoes, &state.shoe, probability * draw_weight);
+            }
+            state.shoe.add_card(&card);
+            state.player_hand.remove_card(&card);
+        }
+    }
+
+    fn collect_hit_shoes_iterative(
+        &mut self,
+        state: &mut GameState<S>,
+        branch_weight: f64,
+        probability: f64,
+        shoes: &mut Vec<(S, f64)>,
+    ) {
+        let mut frames = vec![HitShoesFrame {
+            card: None,
+            branch_weight,
+            probability,
+            draws: state.shoe.get_draws(),
+            next: 0,
+        }];
+
+        while let Some(frame) = frames.last_mut() {
+            let Some(&(card, draw_weight)) = frame.draws.get(frame.next) else {
+                if let Some(card) = frames.pop().unwrap().card {
+                    state.shoe.add_card(&card);
+                    state.player_hand.remove_card(&card);
+                }
+                continue;
+            };
+            frame.next += 1;
+            let weight = frame.branch_weight * draw_weight;
+            if self.is_pruned(frame.branch_weight, draw_weight) {
+                continue;
+            }
+            let probability = frame.probability * draw_weight;
+            state.player_hand.add_card(&card);
+            state.shoe.remove_card(&card);
+            if !state.player_hand.is_bust()
+                && self.can_hit(&state.player_hand)
+                && self.expected_value_hit(state, weight) > self.expected_value_stand(state, weight)
+            {
+                frames.push(HitShoesFrame {
+                    card: Some(card),
+                    branch_weight: weight,
+                    probability,
+                    draws: state.shoe.get_draws(),
+                    next: 0,
+                });
+            } else {
+                record_shoe(shoes, &state.shoe, probability);
+                state.shoe.add_card(&card);
+                state.player_hand.remove_card(&card);
+            }
+        }
+    }
+
+    pub fn expected_value_surrender(&mut self, state: &mut GameState<S>) -> f64 {
+        match self.rules.surrender {
+            SurrenderType::Early => -0.5,
+            SurrenderType::Late if state.dealer_peeked => -0.5,
+            SurrenderType::Late => {
+                let mut hand = Hand::new();
+                hand.add_card(&state.dealer_upcard);
+
+                let mut total_ev = 0.0;
+                let mut total_weight = 0.0;
+
+                for (card, draw_weight) in state.shoe.get_draws() {
+                    hand.add_card(&card);
+
+                    let ev = if hand.is_blackjack() { -1.0 } else { -0.5 };
+                    total_ev += draw_weight * ev;
+                    total_weight += draw_weight;
+
+                    hand.remove_card(&card);
+                }
+
+                total_ev / total_weight
+            }
+            SurrenderType::None => unreachable!(),
+        }
+    }
+
+    pub fn eval_round(
+        &mut self,
+        player_hand: Hand,
+        dealer_upcard: Card,
+        is_pair: bool,
+    ) -> RoundEvs {
+        self.eval_round_with_error(player_hand, dealer_upcard, is_pair)
+            .0
+    }
+
+    // Also bounds how far pruning may have moved any of the EVs from their exact values
+    pub fn eval_round_with_error(
+        &mut self,
+        player_hand: Hand,
+        dealer_upcard: Card,
+        is_pair: bool,
+    ) -> (RoundEvs, f64) {
+        let mut state = GameState {
+            dealer_upcard,
+            player_hand,
+            shoe: self.shoe.clone(),
+            splits: 0,
+            dealer_peeked: self.rules.dealer_checks_blackjack(&dealer_upcard),
+        };
+
+        state.shoe.remove_card(&dealer_upcard);
+        for card in player_hand.cards() {
+            state.shoe.remove_card(&card);
+        }
+
+        self.eval_state(&mut state, is_pair)
+    }
+
+    fn eval_state(&mut self, state: &mut GameState<S>, is_pair: bool) -> (RoundEvs, f64) {
+        let hit = self.estimate_hit(state, 1.0);
+        let stand = self.estimate_stand(state, 1.0);
+        let double = self.estimate_double(state, 1.0);
+        let split = is_pair.then(|| self.estimate_split(state, 1.0));
+        let evs = RoundEvs {
+            hit: hit.ev,
+            stand: stand.ev,
+            double: double.ev,
+            surrender: if self.rules.surrender != SurrenderType::None {
+                Some(self.expected_value_surrender(state))
+            } else {
+                None
+            },
+            split: split.map(|split| split.ev),
+        };
+
+        // Surrendering never depends on pruned branches
+        let error = [Some(hit), Some(stand), Some(double), split]
+            .into_iter()
+            .flatten()
+            .map(|estimate| estimate.error)
+            .fold(0.0, f64::max);
+        (evs, error)
+    }
+
+    pub fn eval_round_distributions(
+        &mut self,
+        player_hand: Hand,
+        dealer_upcard: Card,
+        is_pair: bool,
+    ) -> RoundDistributions {
+        let mut state = GameState {
+            dealer_upcard,
+            player_hand,
+            shoe: self.shoe.clone(),
+            splits: 0,
+            dealer_peeked: self.rules.dealer_checks_blackjack(&dealer_upcard),
+        };
+
+        state.shoe.remove_card(&dealer_upcard);
+        for card in player_hand.cards() {
+            state.shoe.remove_card(&card);
+        }
+
+        RoundDistributions {
+            hit: self.distribution_hit(&mut state, 1.0).outcomes(),
+            stand: self.distribution_stand(&mut state, 1.0).outcomes(),
+            double: self.distribution_double(&mut state, 1.0).outcomes(),
+            surrender: if self.rules.surrender != SurrenderType::None {
+                Some(self.distribution_surrender(&mut state))
+            } else {
+                None
+            },
+            split: if is_pair {
+                Some(self.distribution_split(&mut state, 1.0).outcomes())
+            } else {
+                None
+            },
+        }
+    }
+
+    fn distribution_stand(
+        &mut self,
+        state: &mut GameState<S>,
+        branch_weight: f64,
+    ) -> OutcomesByDealer {
+        let (key, branch_weight) = state.key(&self.options, branch_weight);
+        let key = (key, PlayerAction::Stand);
+        if let Some(item) = self.distribution_cache.get(&key) {
+            return item.clone();
+        }
+        let distribution = self.distribution_settled(state, branch_weight);
+        let grown = self.distribution_cache.insert(key, distribution.clone());
+        self.limit_caches(grown);
+        distribution
+    }
+
+    // Outcomes of the hand as it is against each final dealer hand, a bust hand loses to all
+    // of them
+    fn distribution_settled(
+        &mut self,
+        state: &mut GameState<S>,
+        branch_weight: f64,
+    ) -> OutcomesByDealer {
+        let mut distribution = OutcomesByDealer::new();
+        let mut total_weight = 0.0;
+
+        for (dealer_hand, hand_weight) in self.get_dealer_hands(state) {
+            if self.is_pruned(branch_weight, hand_weight) {
+                continue;
+            }
+            distribution.add(
+                &dealer_hand,
+                self.stand_outcome(state, &dealer_hand),
+                hand_weight,
+            );
+            total_weight += hand_weight;
+        }
+
+        distribution.normalize(total_weight);
+        distribution
+    }
+
+    fn distribution_hit(
+        &mut self,
+        state: &mut GameState<S>,
+        branch_weight: f64,
+    ) -> OutcomesByDealer {
+        let (key, branch_weight) = state.key(&self.options, branch_weight);
+        let key = (key, PlayerAction::Hit);
+        if let Some(item) = self.distribution_cache.get(&key) {
+            return item.clone();
+        }
+        let mut distribution = OutcomesByDealer::new();
+        let mut total_weight = 0.0;
+
+        for (card, draw_weight) in state.shoe.get_draws() {
+            let weight = branch_weight * draw_weight;
+            if self.is_pruned(branch_weight, draw_weight) {
+                continue;
+            }
+            state.player_hand.add_card(&card);
+            state.shoe.remove_card(&card);
+            // A bust hand is still settled against the dealer hand the other hands of a split
+            // face
+            let next = if state.player_hand.is_bust() {
+                self.distribution_settled(state, weight)
+            } else if self.can_hit(&state.player_hand)
+                && self.expected_value_hit(state, weight) > self.expected_value_stand(state, weight)
+            {
+                self.distribution_hit(state, weight)
+            } else {
+                self.distribution_stand(state, weight)
+            };
+            distribution.add_weighted(&next, draw_weight);
+            state.shoe.add_card(&card);
+            state.player_hand.remove_card(&card);
+
+            total_weight += draw_weight;
+        }
+
+        distribution.normalize(total_weight);
+        let grown = self.distribution_cache.insert(key, distribution.clone());
+        self.limit_caches(grown);
+        distribution
+    }
+
+    fn distribution_double(
+        &mut self,
+        state: &mut GameState<S>,
+        branch_weight: f64,
+    ) -> OutcomesByDealer {
+        let (key, branch_weight) = state.key(&self.options, branch_weight);
+        let key = (key, PlayerAction::DoubleOrHit);
+        if let Some(item) = self.distribution_cache.get(&key) {
+            return item.clone();
+        }
+        let mut distribution = OutcomesByDealer::new();
+        let mut total_weight = 0.0;
+
+        for (card, draw_weight) in state.shoe.get_draws() {
+            let weight = branch_weight * draw_weight;
+            if self.is_pruned(branch_weight, draw_weight) {
+                continue;
+            }
+            state.player_hand.add_card(&card);
+            state.shoe.remove_card(&card);
+            let next = self.distribution_stand(state, weight).doubled();
+            distribution.add_weighted(&next, draw_weight);
+            state.shoe.add_card(&card);
+            state.player_hand.remove_card(&card);
+
+            total_weight += draw_weight;
+        }
+
+        distribution.normalize(total_weight);
+        let grown = self.distribution_cache.insert(key, distribution.clone());
+        self.limit_caches(grown);
+        distribution
+    }
+
+    // Both hands are played the same way and drawn from the same shoe, like their EV. They face
+    // the same dealer hand, so their outcomes are only combined as independent for each
+    // final dealer hand
+    fn distribution_split(
+        &mut self,
+        state: &mut GameState<S>,
+        branch_weight: f64,
+    ) -> OutcomesByDealer {
+        let (key, branch_weight) = state.key(&self.options, branch_weight);
+        let key = (key, PlayerAction::Split);
+        if let Some(item) = self.distribution_cache.get(&key) {
+            return item.clone();
+        }
+        let mut hand_distribution = OutcomesByDealer::new();
+        let mut total_weight = 0.0;
+
+        state.split();
+        for (card, draw_weight) in state.shoe.get_draws() {
+            let weight = branch_weight * draw_weight;
+            if self.is_pruned(branch_weight, draw_weight) {
+                continue;
+            }
+            let initial_value = state.player_hand.value();
+            state.player_hand.add_card(&card);
+            state.shoe.remove_card(&card);
+
+            let (action, _) = self.split_hand_action(state, &card, initial_value, weight);
+            let next = match action {
+                PlayerAction::Stand => self.distribution_stand(state, weight),
+                PlayerAction::Hit => self.distribution_hit(state, weight),
+                PlayerAction::Split => self.distribution_split(state, weight),
+                _ => self.distribution_double(state, weight),
+            };
+            hand_distribution.add_weighted(&next, draw_weight);
+
+            state.shoe.add_card(&card);
+            state.player_hand.remove_card(&card);
+
+            total_weight += draw_weight;
+        }
+        state.unsplit();
+
+        hand_distribution.normalize(total_weight);
+        let distribution = hand_distribution.split_hands();
+        let grown = self.distribution_cache.insert(key, distribution.clone());
+        self.limit_caches(grown);
+        distribution
+    }
+
+    fn distribution_surrender(&mut self, state: &mut GameState<S>) -> OutcomeDistribution {
+        let mut distribution = OutcomeDistribution::new();
+        match self.rules.surrender {
+            SurrenderType::Late if !state.dealer_peeked => {
+                // A dealer natural still costs the whole bet
+                let ev = self.expected_value_surrender(state);
+                let natural_probability = (ev + 0.5) / -0.5;
+                if natural_probability > 0.0 {
+                    distribution.add(-1.0, natural_probability);
+                }
+                distribution.add(-0.5, 1.0 - natural_probability);
+            }
+            _ => distribution.add(-0.5, 1.0),
+        }
+        distribution
+    }
+
+    pub fn expected_return(&mut self) -> f64 {
+        let mut shoe = self.shoe.clone();
+        let mut total_ev = 0.0;
+        let mut total_weight = 0.0;
+
+        for (dealer_upcard, upcard_weight) in shoe.get_draws() {
+            if upcard_weight <= 0.0 {
+                continue;
+            }
+            shoe.remove_card(&dealer_upcard);
+            for (first, first_weight) in shoe.get_draws() {
+                if first_weight <= 0.0 {
+                    continue;
+                }
+                shoe.remove_card(&first);
+                for (second, second_weight) in shoe.get_draws() {
+                    // Each unordered pair of cards is visited once, and counted twice when
+                    // the two cards differ
+                    if second_weight <= 0.0 || second.rank.value() < first.rank.value() {
+                        continue;
+                    }
+                    let orderings = if first.rank == second.rank { 1.0 } else { 2.0 };
+                    let weight = upcard_weight * first_weight * second_weight * orderings;
+
+                    shoe.remove_card(&second);
+                    let ev =
+                        self.expected_value_initial_hand(&first, &second, dealer_upcard, &shoe);
+                    shoe.add_card(&second);
+
+                    total_ev += ev * weight;
+                    total_weight += weight;
+                }
+                shoe.add_card(&first);
+            }
+            shoe.add_card(&dealer_upcard);
+        }
+
+        total_ev / total_weight
+    }
+
+    fn expected_value_initial_hand(
+        &mut self,
+        first: &Card,
+        second: &Card,
+        dealer_upcard: Card,
+        shoe: &S,
+    ) -> f64 {
+        let mut player_hand = Hand::new();
+        player_hand.add_card(first);
+        player_hand.add_card(second);
+
+        let mut dealer_hand = Hand::new();
+        dealer_hand.add_card(&dealer_upcard);
+        let mut dealer_blackjack = 0.0;
+        let mut total_weight = 0.0;
+        for (card, draw_weight) in shoe.get_draws() {
+            dealer_hand.add_card(&card);
+            if dealer_hand.is_blackjack() {
+                dealer_blackjack += draw_weight;
+            }
+            total_weight += draw_weight;
+            dealer_hand.remove_card(&card);
+        }
+        dealer_blackjack /= total_weight;
+
+        if player_hand.is_blackjack() {
+            return (1.0 - dealer_blackjack) * self.rules.blackjack_payout.ratio();
+        }
+
+        let dealer_peeked = self.rules.dealer_checks_blackjack(&dealer_upcard);
+        let mut state = GameState {
+            dealer_upcard,
+            player_hand,
+            shoe: shoe.clone(),
+            splits: 0,
+            dealer_peeked,
+        };
+        let (evs, _) = self.eval_state(&mut state, first.rank == second.rank);
+        let (_, best_ev) = evs.best();
+
+        // Insurance is always declined, so a dealer natural only costs the initial bet when
+        // the dealer peeks, and everything that was wagered otherwise
+        let ev = if dealer_peeked {
+            (1.0 - dealer_blackjack) * best_ev - dealer_blackjack
+        } else {
+            best_ev
+        };
+        if self.rules.surrender == SurrenderType::Early {
+            ev.max(-0.5)
+        } else {
+            ev
+        }
+    }
+
+    pub fn table(&mut self, table: DecisionTable) -> StrategyTable {
+        let mut tables = self
+            .tables_with_progress(&[table], |_| ControlFlow::Continue(()))
+            .expect("generation is never cancelled");
+        tables.pop().expect("one table was requested")
+    }
+
+    // Solves the tables in order, reporting every cell solved. Cancelling keeps the cells
+    // solved so far in the caches, so generating the tables again resumes from them
+    pub fn tables_with_progress(
+        &mut self,
+        tables: &[DecisionTable],
+        mut progress: impl FnMut(Progress) -> ControlFlow<()>,
+    ) -> Result<Vec<StrategyTable>, Error> {
+        let mut done = Progress::of(tables);
+        let mut results = Vec::with_capacity(tables.len());
+
+        for &table in tables {
+            let (from, to) = table.player_bounds();
+            let mut result = StrategyTable::new(from, to);
+            for player_value in result.player_value_range() {
+                for dealer_value in result.dealer_value_range() {
+                    let value = self.table_cell(table, player_value, dealer_value);
+                    result.set(player_value, dealer_value, value);
+                    done.done += 1;
+                    if progress(done).is_break() {
+                        return Err(Error::Cancelled);
+                    }
+                }
+            }
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+
+    pub fn hard_table(&mut self) -> StrategyTable {
+        self.table(DecisionTable::Hard)
+    }
+
+    pub fn soft_table(&mut self) -> StrategyTable {
+        self.table(DecisionTable::Soft)
     }
 
     pub fn pair_table(&mut self) -> StrategyTable {
-        let mut table = StrategyTable::new(2, 11);
+        self.table(DecisionTable::Pair)
+    }
 
-        for player_value in table.player_value_range() {
-            for dealer_value in table.dealer_value_range() {
-                let dealer_upcard = Card {
-                    rank: Rank::from_value(dealer_value),
-                };
+    fn table_cell(
+        &mut self,
+        table: DecisionTable,
+        player_value: u8,
+        dealer_value: u8,
+    ) -> StrategyValue {
+        let dealer_upcard = Card {
+            rank: Rank::from_value(dealer_value).expect("dealer values are card values"),
+        };
+        let hand_type = match table {
+            DecisionTable::Hard => HandType::Hard,
+            DecisionTable::Soft => HandType::Soft,
+            DecisionTable::Pair => {
+                let player_hand = Hand::pair_from_single_value(player_value)
+                    .expect("pair values are card values");
+                let (evs, error) = self.eval_round_with_error(player_hand, dealer_upcard, true);
+                return StrategyValue::from_evs(evs, error);
+            }
+        };
 
-                let player_hand = Hand::pair_from_single_value(player_value);
+        // Each cell averages every composition that reaches its total
+        let mut evs = Vec::new();
+        let mut error = 0.0;
+        let mut total_weight = 0.0;
+        for (player_hand, weight) in self.total_hands(player_value, hand_type, dealer_upcard) {
+            let (hand_evs, hand_error) =
+                self.eval_round_with_error(player_hand, dealer_upcard, false);
+            evs.push((hand_evs, weight));
+            error += hand_error * weight;
+            total_weight += weight;
+        }
+        StrategyValue::from_evs(RoundEvs::weighted_average(&evs), error / total_weight)
+    }
 
-                let evs = self.eval_round(player_hand, dealer_upcard, true);
-                let value = StrategyValue::from_evs(evs);
+    fn total_hands(
+        &self,
+        player_value: u8,
+        hand_type: HandType,
+        dealer_upcard: Card,
+    ) -> Vec<(Hand, f64)> {
+        let mut shoe = self.shoe.clone();
+        shoe.remove_card(&dealer_upcard);
+        shoe.iter_player_hands(player_value, hand_type).collect()
+    }
 
-                table.set(player_value, dealer_value, value);
+    // Outcome distributions of the actions of a table cell, for a total it mixes every
+    // composition reaching it
+    pub fn cell_distributions(
+        &mut self,
+        table: DecisionTable,
+        player_value: u8,
+        dealer_value: u8,
+    ) -> RoundDistributions {
+        let dealer_upcard = Card {
+            rank: Rank::from_value(dealer_value).expect("dealer values are card values"),
+        };
+        let (hands, is_pair) = match table {
+            DecisionTable::Hard => (
+                self.total_hands(player_value, HandType::Hard, dealer_upcard),
+                false,
+            ),
+            DecisionTable::Soft => (
+                self.total_hands(player_value, HandType::Soft, dealer_upcard),
+                false,
+            ),
+            DecisionTable::Pair => {
+                let hand = Hand::pair_from_single_value(player_value)
+                    .expect("pair values are card values");
+                (vec![(hand, 1.0)], true)
             }
+        };
+
+        let distributions: Vec<_> = hands
+            .into_iter()
+            .map(|(hand, weight)| {
+                (
+                    self.eval_round_distributions(hand, dealer_upcard, is_pair),
+                    weight,
+                )
+            })
+            .collect();
+        RoundDistributions::weighted_average(&distributions)
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<S: Shoe + Clone + Eq + Hash + Send> StrategyGenerator<S> {
+    // Every cache entry belongs to a single dealer upcard, so each column is solved by a
+    // generator holding that upcard's entries, in the order `table` solves it. This keeps the
+    // results identical to `table`. A thread count of 0 uses one thread per core
+    pub fn table_parallel(
+        &mut self,
+        table: DecisionTable,
+        threads: usize,
+    ) -> Result<StrategyTable, Error> {
+        let mut tables = self.tables_parallel(&[table], threads, |_| ControlFlow::Continue(()))?;
+        Ok(tables.pop().expect("one table was requested"))
+    }
+
+    // Parallel `tables_with_progress`, the progress is reported from whichever thread solved
+    // the cell. Cells other threads are solving when it cancels are still reported
+    pub fn tables_parallel(
+        &mut self,
+        tables: &[DecisionTable],
+        threads: usize,
+        progress: impl FnMut(Progress) -> ControlFlow<()> + Send,
+    ) -> Result<Vec<StrategyTable>, Error> {
+        use rayon::prelude::*;
+        use std::sync::{
+            Mutex,
+            atomic::{AtomicBool, Ordering},
+        };
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|_| Error::ThreadPool)?;
+
+        let progress = Mutex::new((Progress::of(tables), progress));
+        let cancelled = AtomicBool::new(false);
+        let solve_cell = |generator: &mut Self, table, player_value, dealer_value| {
+            if cancelled.load(Ordering::Relaxed) {
+                return None;
+            }
+            let value = generator.table_cell(table, player_value, dealer_value);
+            let mut progress = progress.lock().unwrap();
+            let (done, report) = &mut *progress;
+            done.done += 1;
+            if report(*done).is_break() {
+                cancelled.store(true, Ordering::Relaxed);
+            }
+            Some(value)
+        };
+
+        let mut columns = self.take_columns();
+        let values: Vec<Vec<Option<StrategyValue>>> = pool.install(|| {
+            columns
+                .par_iter_mut()
+                .map(|(dealer_value, generator)| {
+                    let mut column = Vec::new();
+                    for &table in tables {
+                        let (from, to) = table.player_bounds();
+                        for player_value in from..=to {
+                            column.push(solve_cell(generator, table, player_value, *dealer_value));
+                        }
+                    }
+                    column
+                })
+                .collect()
+        });
+
+        // The caches are merged back even when cancelled, they only hold solved states
+        let mut results: Vec<_> = tables
+            .iter()
+            .map(|table| {
+                let (from, to) = table.player_bounds();
+                StrategyTable::new(from, to)
+            })
+            .collect();
+        for ((dealer_value, generator), column) in columns.into_iter().zip(values) {
+            let mut column = column.into_iter();
+            for (&table, result) in tables.iter().zip(&mut results) {
+                let (from, to) = table.player_bounds();
+                for (player_value, value) in (from..=to).zip(&mut column) {
+                    if let Some(value) = value {
+                        result.set(player_value, dealer_value, value);
+                    }
+                }
+            }
+            self.absorb(generator);
+        }
+
+        if cancelled.into_inner() {
+            return Err(Error::Cancelled);
+        }
+        Ok(results)
+    }
+
+    // Moves the cache entries of each dealer upcard to a generator of its own
+    fn take_columns(&mut self) -> Vec<(u8, Self)> {
+        let mut columns: Vec<_> = (2..=11)
+            .map(|dealer_value| {
+                let mut generator = Self::with_options(self.rules, self.shoe.clone(), self.options)
+                    .expect("options were validated");
+                // The columns share the limit, which does not change the values they solve
+                generator.cache_limit = self.cache_limit.map(|limit| limit / 10);
+                (dealer_value, generator)
+            })
+            .collect();
+
+        for (key, estimate) in self.hit_cache.drain() {
+            let generator = &mut columns[key.upcard_index()].1;
+            generator.hit_cache.insert(key, estimate);
+        }
+        for (key, estimate) in self.stand_cache.drain() {
+            let generator = &mut columns[key.upcard_index()].1;
+            generator.stand_cache.insert(key, estimate);
+        }
+        for (key, estimate) in self.double_cache.drain() {
+            let generator = &mut columns[key.upcard_index()].1;
+            generator.double_cache.insert(key, estimate);
+        }
+        for (key, estimate) in self.split_cache.drain() {
+            let generator = &mut columns[key.upcard_index()].1;
+            generator.split_cache.insert(key, estimate);
+        }
+        for (key, hands) in self.dealer_hand_cache.drain() {
+            let generator = &mut columns[key.upcard].1;
+            generator.dealer_hand_cache.insert(key, hands);
+        }
+        for (key, distribution) in self.distribution_cache.drain() {
+            let generator = &mut columns[key.0.upcard_index()].1;
+            generator.distribution_cache.insert(key, distribution);
+        }
+        for (_, generator) in &mut columns {
+            generator.recount_caches();
+        }
+        self.recent_bytes = 0;
+
+        columns
+    }
+
+    fn absorb(&mut self, other: Self) {
+        self.hit_cache.absorb(other.hit_cache);
+        self.stand_cache.absorb(other.stand_cache);
+        self.double_cache.absorb(other.double_cache);
+        self.split_cache.absorb(other.split_cache);
+        self.dealer_hand_cache.absorb(other.dealer_hand_cache);
+        self.distribution_cache.absorb(other.distribution_cache);
+        self.recount_caches();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecisionTable {
+    Hard,
+    Soft,
+    Pair,
+}
+
+impl DecisionTable {
+    pub const ALL: [DecisionTable; 3] = [
+        DecisionTable::Hard,
+        DecisionTable::Soft,
+        DecisionTable::Pair,
+    ];
+
+    pub(crate) fn player_bounds(self) -> (u8, u8) {
+        match self {
+            DecisionTable::Hard => (5, 21),
+            DecisionTable::Soft => (13, 21),
+            DecisionTable::Pair => (2, 11),
         }
+    }
+}
 
-        table
+// Cells solved so far out of every cell of the tables being generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify), tsify(into_wasm_abi))]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    fn of(tables: &[DecisionTable]) -> Self {
+        let total = tables
+            .iter()
+            .map(|table| {
+                let (from, to) = table.player_bounds();
+                usize::from(to - from + 1) * 10
+            })
+            .sum();
+        Progress { done: 0, total }
     }
 }
 
+// Cell of a table solved by a `CellStream`
 #[derive(Clone, Copy)]
+pub struct TableCell {
+    pub table: DecisionTable,
+    pub player_value: u8,
+    pub dealer_value: u8,
+    pub value: StrategyValue,
+}
+
+// Resumable generation of the cells of tables, in the order `table` solves them. Every call
+// solves the next cell with the given generator, so callers may render it or yield in between
+pub struct CellStream {
+    tables: Vec<DecisionTable>,
+    progress: Progress,
+}
+
+impl CellStream {
+    pub fn new(tables: &[DecisionTable]) -> Self {
+        CellStream {
+            tables: tables.to_vec(),
+            progress: Progress::of(tables),
+        }
+    }
+
+    pub fn progress(&self) -> Progress {
+        self.progress
+    }
+
+    // Table, player value and dealer value of the next cell to solve
+    fn position(&self) -> Option<(DecisionTable, u8, u8)> {
+        let mut index = self.progress.done;
+        for &table in &self.tables {
+            let (from, to) = table.player_bounds();
+            let cells = usize::from(to - from + 1) * 10;
+            if index < cells {
+                return Some((table, from + (index / 10) as u8, 2 + (index % 10) as u8));
+            }
+            index -= cells;
+        }
+        None
+    }
+
+    pub fn next_cell<S: Shoe + Clone + Eq + Hash>(
+        &mut self,
+        generator: &mut StrategyGenerator<S>,
+    ) -> Option<TableCell> {
+        let (table, player_value, dealer_value) = self.position()?;
+        let value = generator.table_cell(table, player_value, dealer_value);
+        self.progress.done += 1;
+        Some(TableCell {
+            table,
+            player_value,
+            dealer_value,
+            value,
+        })
+    }
+
+    // Cells left in the current row, all ten of them unless `next_cell` stopped inside it
+    pub fn next_row<S: Shoe + Clone + Eq + Hash>(
+        &mut self,
+        generator: &mut StrategyGenerator<S>,
+    ) -> Option<Vec<TableCell>> {
+        let mut row = Vec::with_capacity(10);
+        while let Some(cell) = self.next_cell(generator) {
+            row.push(cell);
+            if cell.dealer_value == 11 {
+                break;
+            }
+        }
+        (!row.is_empty()).then_some(row)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "tsify",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
 pub struct StrategyValue {
     pub ev: f64,
     pub evs: RoundEvs,
     pub action: PlayerAction,
+    // Bound on how far pruning may have moved any of the EVs from their exact values
+    pub error: f64,
 }
 
 impl StrategyValue {
-    fn from_evs(evs: RoundEvs) -> Self {
+    pub(crate) fn from_evs(evs: RoundEvs, error: f64) -> Self {
         let (action, ev) = evs.best();
-        Self { action, ev, evs }
+        Self {
+            action,
+            ev,
+            evs,
+            error,
+        }
     }
 }
 
+// Values are stored row by row, a row per player value from `from` to `to` holding the dealer
+// values 2 to 11
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "tsify",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
 pub struct StrategyTable {
     pub values: Vec<StrategyValue>,
     pub from: u8,
@@ -445,7 +1915,8 @@ impl StrategyTable {
                         double: 0.0,
                         surrender: None,
                         split: None,
-                    }
+                    },
+                    error: 0.0,
                 };
                 size
             ],
@@ -455,7 +1926,7 @@ impl StrategyTable {
     fn index(&self, player_value: u8, dealer_value: u8) -> usize {
         let x = (dealer_value - 2) as usize;
         let y = (player_value - self.from) as usize;
-        return x + y * 10;
+        x + y * 10
     }
 
     pub fn set(&mut self, player_value: u8, dealer_value: u8, value: StrategyValue) {
@@ -469,11 +1940,11 @@ impl StrategyTable {
     }
 
     pub fn player_from(&self) -> u8 {
-        return self.from;
+        self.from
     }
 
     pub fn player_to(&self) -> u8 {
-        return self.to;
+        self.to
     }
 
     pub fn player_value_range(&self) -> RangeInclusive<u8> {
@@ -484,3 +1955,433 @@ impl StrategyTable {
         2..=11
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        options::{PruningStrategy, Solver},
+        shoe::{CountShoe, InfiniteShoe},
+    };
+
+    fn card(value: u8) -> Card {
+        Card::from_rank(Rank::from_value(value).unwrap())
+    }
+
+    fn hand(values: &[u8]) -> Hand {
+        let cards: Vec<_> = values.iter().map(|&value| card(value)).collect();
+        Hand::from_cards(&cards)
+    }
+
+    #[test]
+    fn expected_return_of_the_default_rules() {
+        let mut generator = StrategyGenerator::new(Rules::default(), InfiniteShoe::new()).unwrap();
+        let edge = generator.expected_return();
+        assert!((edge - -0.004159).abs() < 1e-5, "{edge}");
+
+        // Without pruning nothing is left out
+        let options = GeneratorOptions {
+            pruning: PruningStrategy::None,
+            ..GeneratorOptions::default()
+        };
+        let mut generator =
+            StrategyGenerator::with_options(Rules::default(), InfiniteShoe::new(), options)
+                .unwrap();
+        let edge = generator.expected_return();
+        assert!((edge - -0.003950).abs() < 1e-6, "{edge}");
+    }
+
+    #[test]
+    fn split_hand_twenty_one_pays_even_money() {
+        let generator = StrategyGenerator::new(Rules::default(), InfiniteShoe::new()).unwrap();
+        let mut state = GameState {
+            player_hand: hand(&[11, 10]),
+            dealer_upcard: card(10),
+            shoe: InfiniteShoe::new(),
+            splits: 0,
+            dealer_peeked: true,
+        };
+        let dealer_hand = hand(&[10, 8]);
+        assert_eq!(generator.stand_outcome(&state, &dealer_hand), 1.5);
+
+        state.splits = 1;
+        assert_eq!(generator.stand_outcome(&state, &dealer_hand), 1.0);
+        // Nor does it push against a dealer natural
+        assert_eq!(generator.stand_outcome(&state, &hand(&[10, 11])), -1.0);
+    }
+
+    // Infinite shoe of four ranks, few enough to play every split out by brute force
+    #[derive(Clone, Hash, PartialEq, Eq)]
+    struct TinyShoe;
+
+    impl Shoe for TinyShoe {
+        const REMOVES_CARDS: bool = false;
+
+        fn key(&self) -> u64 {
+            0
+        }
+
+        fn can_reach(&self, _key: u64) -> bool {
+            true
+        }
+
+        fn get_draws(&self) -> Vec<(Card, f64)> {
+            vec![
+                (card(2), 0.3),
+                (card(6), 0.2),
+                (card(10), 0.3),
+                (card(11), 0.2),
+            ]
+        }
+
+        fn iter_player_hands(
+            &self,
+            _player_value: u8,
+            _hand_type: HandType,
+        ) -> impl Iterator<Item = (Hand, f64)> {
+            std::iter::empty()
+        }
+
+        fn composition_weight(&self, _hand: &Hand) -> f64 {
+            unimplemented!("tables are not solved with this shoe")
+        }
+
+        fn remove_card(&mut self, _card: &Card) {}
+
+        fn add_card(&mut self, _card: &Card) {}
+    }
+
+    // Final hands a hit hand ends with, along with their probability
+    fn hit_hands(
+        generator: &mut StrategyGenerator<TinyShoe>,
+        state: &mut GameState<TinyShoe>,
+        probability: f64,
+        hands: &mut Vec<(Hand, f64, f64)>,
+    ) {
+        for (card, draw_weight) in TinyShoe.get_draws() {
+            state.player_hand.add_card(&card);
+            if !state.player_hand.is_bust()
+                && generator.can_hit(&state.player_hand)
+                && generator.expected_value_hit(state, 1.0)
+                    > generator.expected_value_stand(state, 1.0)
+            {
+                hit_hands(generator, state, probability * draw_weight, hands);
+            } else {
+                hands.push((state.player_hand, 1.0, probability * draw_weight));
+            }
+            state.player_hand.remove_card(&card);
+        }
+    }
+
+    // Final hands of one hand of the split pair, with the bet on them and their probability
+    fn split_hands(
+        generator: &mut StrategyGenerator<TinyShoe>,
+        state: &mut GameState<TinyShoe>,
+    ) -> Vec<(Hand, f64, f64)> {
+        let mut hands = Vec::new();
+        state.split();
+        let initial_value = state.player_hand.value();
+        for (card, draw_weight) in TinyShoe.get_draws() {
+            state.player_hand.add_card(&card);
+            match generator
+                .split_hand_action(state, &card, initial_value, 1.0)
+                .0
+            {
+                PlayerAction::Stand => hands.push((state.player_hand, 1.0, draw_weight)),
+                PlayerAction::Hit => hit_hands(generator, state, draw_weight, &mut hands),
+                PlayerAction::DoubleOrHit => {
+                    for (next, next_weight) in TinyShoe.get_draws() {
+                        let mut hand = state.player_hand;
+                        hand.add_card(&next);
+                        hands.push((hand, 2.0, draw_weight * next_weight));
+                    }
+                }
+                action => panic!("{action:?} cannot be played without resplitting"),
+            }
+            state.player_hand.remove_card(&card);
+        }
+        state.unsplit();
+        hands
+    }
+
+    // Final dealer hands once the dealer peeked under a ten
+    fn peeked_dealer_hands(rules: &Rules, upcard: Card) -> Vec<(Hand, f64)> {
+        let draws = TinyShoe.get_draws();
+        let mut stack = Vec::new();
+        for &(card, draw_weight) in &draws {
+            let hand = Hand::from_cards(&[upcard, card]);
+            if !hand.is_blackjack() {
+                stack.push((hand, draw_weight));
+            }
+        }
+        let no_blackjack: f64 = stack.iter().map(|(_, weight)| weight).sum();
+
+        let mut hands = Vec::new();
+        while let Some((hand, weight)) = stack.pop() {
+            if rules.dealer_must_stand(&hand) {
+                hands.push((hand, weight / no_blackjack));
+                continue;
+            }
+            for &(card, draw_weight) in &draws {
+                let mut next = hand;
+                next.add_card(&card);
+                stack.push((next, weight * draw_weight));
+            }
+        }
+        hands
+    }
+
+    #[test]
+    fn split_variance_matches_brute_force() {
+        let rules = Rules {
+            double_after_split_allowed: true,
+            max_splits: 1,
+            ..Rules::default()
+        };
+        let options = GeneratorOptions {
+            pruning: PruningStrategy::None,
+            ..GeneratorOptions::default()
+        };
+        let mut generator = StrategyGenerator::with_options(rules, TinyShoe, options).unwrap();
+        let upcard = card(10);
+        let mut state = GameState {
+            player_hand: hand(&[8, 8]),
+            dealer_upcard: upcard,
+            shoe: TinyShoe,
+            splits: 0,
+            dealer_peeked: true,
+        };
+
+        // Both hands are settled against the same dealer hand
+        let hands = split_hands(&mut generator, &mut state);
+        let settle =
+            |(hand, bet, _): &(Hand, f64, f64), dealer_hand: &Hand| match Hand::compare_split(
+                hand,
+                dealer_hand,
+            ) {
+                Outcome::Win => *bet,
+                Outcome::Push => 0.0,
+                Outcome::Lose => -bet,
+            };
+        let mut moments = [0.0; 3];
+        for (dealer_hand, dealer_weight) in peeked_dealer_hands(&rules, upcard) {
+            for first in &hands {
+                for second in &hands {
+                    let probability = dealer_weight * first.2 * second.2;
+                    let outcome = settle(first, &dealer_hand) + settle(second, &dealer_hand);
+                    moments[0] += probability;
+                    moments[1] += probability * outcome;
+                    moments[2] += probability * outcome * outcome;
+                }
+            }
+        }
+        let mean = moments[1] / moments[0];
+        let variance = moments[2] / moments[0] - mean * mean;
+
+        let distribution = generator.distribution_split(&mut state, 1.0).outcomes();
+        assert!((moments[0] - 1.0).abs() < 1e-12);
+        assert!((distribution.mean() - mean).abs() < 1e-12);
+        assert!((distribution.variance() - variance).abs() < 1e-12);
+        let ev = generator.expected_value_split(&mut state, 1.0);
+        assert!((ev - mean).abs() < 1e-12);
+    }
+
+    // Coarse enough to solve whole tables quickly, branch pruning still drops some states
+    fn one_deck_generator(options: GeneratorOptions) -> StrategyGenerator<CountShoe> {
+        let rules = Rules {
+            num_decks: 1,
+            ..Rules::default()
+        };
+        let options = GeneratorOptions {
+            epsilon: 1e-3,
+            ..options
+        };
+        StrategyGenerator::with_options(rules, CountShoe::new(1).unwrap(), options).unwrap()
+    }
+
+    #[test]
+    fn cache_limit_keeps_the_values() {
+        let mut unbounded = one_deck_generator(GeneratorOptions::default());
+        let expected = unbounded.table(DecisionTable::Hard);
+        let limit = unbounded.cache_stats().bytes / 8;
+
+        let mut bounded = one_deck_generator(GeneratorOptions::default());
+        bounded.set_cache_limit(Some(limit));
+        let table = bounded.table(DecisionTable::Hard);
+        // Evicted states are solved again with the same pruning, so the values do not even
+        // move within their error bound
+        for (value, expected) in table.values.iter().zip(&expected.values) {
+            assert!((value.ev - expected.ev).abs() <= value.error.min(expected.error));
+        }
+        assert_eq!(table, expected);
+        assert!(bounded.cache_stats().bytes <= limit);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn cache_limit_keeps_parallel_values() {
+        let mut unbounded = one_deck_generator(GeneratorOptions::default());
+        unbounded.table(DecisionTable::Hard);
+        let limit = unbounded.cache_stats().bytes / 8;
+
+        let mut serial = one_deck_generator(GeneratorOptions::default());
+        let mut parallel = one_deck_generator(GeneratorOptions::default());
+        serial.set_cache_limit(Some(limit));
+        parallel.set_cache_limit(Some(limit));
+        // Each column only gets a tenth of the limit
+        assert_eq!(
+            parallel.table_parallel(DecisionTable::Hard, 2).unwrap(),
+            serial.table(DecisionTable::Hard)
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_tables_match_serial() {
+        let mut serial = one_deck_generator(GeneratorOptions::default());
+        let expected = DecisionTable::ALL.map(|table| serial.table(table));
+
+        let mut parallel = one_deck_generator(GeneratorOptions::default());
+        let tables = parallel
+            .tables_parallel(&DecisionTable::ALL, 0, |_| ControlFlow::Continue(()))
+            .unwrap();
+        assert_eq!(tables, expected);
+        // Columns solved again from the merged caches give the same values
+        assert_eq!(
+            parallel.table_parallel(DecisionTable::Pair, 3).unwrap(),
+            expected[2]
+        );
+    }
+
+    #[test]
+    fn iterative_solver_matches_recursive() {
+        for max_depth in [None, Some(4)] {
+            let mut generators = [Solver::Recursive, Solver::Iterative].map(|solver| {
+                one_deck_generator(GeneratorOptions {
+                    max_depth,
+                    solver,
+                    ..GeneratorOptions::default()
+                })
+            });
+            let [recursive, iterative] = &mut generators;
+            for table in DecisionTable::ALL {
+                assert_eq!(iterative.table(table), recursive.table(table));
+            }
+            assert_eq!(
+                iterative.expected_return().to_bits(),
+                recursive.expected_return().to_bits()
+            );
+        }
+    }
+
+    #[test]
+    fn removed_cards_keep_the_values() {
+        let removed = [10, 10, 10, 5].map(card);
+        let mut generator = one_deck_generator(GeneratorOptions::default());
+        generator.table(DecisionTable::Hard);
+        for card in &removed {
+            generator.remove_card(card).unwrap();
+            generator.table(DecisionTable::Hard);
+        }
+
+        let mut shoe = CountShoe::new(1).unwrap();
+        for card in &removed {
+            shoe.remove_card(card);
+        }
+        let mut fresh =
+            StrategyGenerator::with_options(*generator.rules(), shoe, *generator.options())
+                .unwrap();
+        assert_eq!(
+            generator.table(DecisionTable::Hard),
+            fresh.table(DecisionTable::Hard)
+        );
+    }
+
+    #[test]
+    fn cancelled_tables_resume() {
+        let expected = one_deck_generator(GeneratorOptions::default()).table(DecisionTable::Hard);
+
+        let mut generator = one_deck_generator(GeneratorOptions::default());
+        let cancelled = generator.tables_with_progress(&[DecisionTable::Hard], |progress| {
+            if progress.done == progress.total / 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(cancelled, Err(Error::Cancelled));
+        assert_eq!(generator.table(DecisionTable::Hard), expected);
+    }
+
+    #[test]
+    fn cell_stream_matches_the_table() {
+        let expected = one_deck_generator(GeneratorOptions::default()).table(DecisionTable::Hard);
+
+        let mut generator = one_deck_generator(GeneratorOptions::default());
+        let mut stream = CellStream::new(&[DecisionTable::Hard]);
+        let (from, to) = DecisionTable::Hard.player_bounds();
+        let mut table = StrategyTable::new(from, to);
+        while let Some(cell) = stream.next_cell(&mut generator) {
+            assert_eq!(cell.table, DecisionTable::Hard);
+            table.set(cell.player_value, cell.dealer_value, cell.value);
+        }
+        assert_eq!(stream.progress().done, stream.progress().total);
+        assert_eq!(table, expected);
+    }
+
+    #[test]
+    fn zero_epsilon_matches_no_pruning() {
+        let rules = Rules {
+            num_decks: 1,
+            ..Rules::default()
+        };
+        let eval = |pruning| {
+            let options = GeneratorOptions {
+                epsilon: 0.0,
+                pruning,
+                ..GeneratorOptions::default()
+            };
+            let mut generator =
+                StrategyGenerator::with_options(rules, CountShoe::new(1).unwrap(), options)
+                    .unwrap();
+            generator.eval_round_with_error(hand(&[2, 2]), card(10), true)
+        };
+
+        let (expected, _) = eval(PruningStrategy::None);
+        for pruning in [PruningStrategy::Branch, PruningStrategy::Draw] {
+            let (evs, error) = eval(pruning);
+            assert_eq!(evs, expected);
+            assert!(error < 1e-12);
+        }
+    }
+
+    #[test]
+    fn dealer_peek_conditions_the_round() {
+        let rules = Rules {
+            surrender: SurrenderType::Late,
+            ..Rules::default()
+        };
+        let (first, second, upcard) = (card(10), card(6), card(11));
+        let player_hand = hand(&[10, 6]);
+        let blackjack = 4.0 / 13.0;
+
+        // Play only goes on once the dealer has no natural, which then only costs the bet
+        let mut peek = StrategyGenerator::new(rules, InfiniteShoe::new()).unwrap();
+        let evs = peek.eval_round(player_hand, upcard, false);
+        assert_eq!(evs.surrender, Some(-0.5));
+        let initial = peek.expected_value_initial_hand(&first, &second, upcard, &InfiniteShoe);
+        assert!((initial - ((1.0 - blackjack) * evs.best().1 - blackjack)).abs() < 1e-12);
+
+        // Without a peek the cells already include the dealer naturals
+        let rules = Rules {
+            dealer_peek: false,
+            ..rules
+        };
+        let mut no_peek = StrategyGenerator::new(rules, InfiniteShoe::new()).unwrap();
+        let evs = no_peek.eval_round(player_hand, upcard, false);
+        let surrender = -0.5 * (1.0 - blackjack) - blackjack;
+        assert!((evs.surrender.unwrap() - surrender).abs() < 1e-12);
+        let initial = no_peek.expected_value_initial_hand(&first, &second, upcard, &InfiniteShoe);
+        assert!((initial - evs.best().1).abs() < 1e-12);
+    }
+}
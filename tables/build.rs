@@ -0,0 +1,16 @@
+use std::{
+    env,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+// Solves the tables of the common presets once per build, the crate embeds them from OUT_DIR
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    let path = Path::new(&env::var("OUT_DIR").expect("cargo sets OUT_DIR")).join("tables.bin");
+    let mut writer = BufWriter::new(File::create(path).expect("OUT_DIR is writable"));
+    engine::precomputed::generate(&mut writer)
+        .and_then(|()| writer.flush())
+        .expect("writing the precomputed tables failed");
+}
@@ -0,0 +1,12 @@
+use std::sync::OnceLock;
+
+use engine::precomputed::PrecomputedTables;
+
+// Written by the build script for the common presets
+const TABLES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/tables.bin"));
+
+// Parsed on first use and shared by every later call
+pub fn precomputed_tables() -> &'static PrecomputedTables {
+    static PARSED: OnceLock<PrecomputedTables> = OnceLock::new();
+    PARSED.get_or_init(|| PrecomputedTables::parse(TABLES).expect("the build embeds valid tables"))
+}
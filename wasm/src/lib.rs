@@ -1,5 +1,15 @@
-use engine::{card, hand::Hand, rules, shoe::InfiniteShoe, strategy};
+use engine::{
+    cache::CacheStats,
+    card,
+    dealer::DealerTable,
+    hand::Hand,
+    options::GeneratorOptions,
+    rules,
+    shoe::{CountShoe, InfiniteShoe},
+    strategy::{self, PlayerAction, Progress, RoundEvs, StrategyTable, StrategyValue},
+};
 use serde::{Deserialize, Serialize};
+use std::ops::ControlFlow;
 use tsify::Tsify;
 use wasm_bindgen::prelude::*;
 
@@ -43,159 +53,60 @@ impl From<Rank> for card::Rank {
 
 #[derive(Tsify, Serialize, Deserialize)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
-pub enum PlayerAction {
-    Hit,
-    Stand,
-    DoubleOrHit,
-    DoubleOrStand,
-    Split,
-    Surrender,
+pub enum DecisionTable {
+    Hard,
+    Soft,
+    Pair,
 }
 
-impl From<strategy::PlayerAction> for PlayerAction {
-    fn from(value: strategy::PlayerAction) -> Self {
+impl From<strategy::DecisionTable> for DecisionTable {
+    fn from(value: strategy::DecisionTable) -> Self {
         match value {
-            strategy::PlayerAction::Hit => Self::Hit,
-            strategy::PlayerAction::Stand => Self::Stand,
-            strategy::PlayerAction::DoubleOrHit => Self::DoubleOrHit,
-            strategy::PlayerAction::DoubleOrStand => Self::DoubleOrStand,
-            strategy::PlayerAction::Split => Self::Split,
-            strategy::PlayerAction::Surrender => Self::Surrender,
-        }
-    }
-}
-
-#[derive(Tsify, Serialize, Deserialize)]
-pub enum BlackjackPayout {
-    Ratio3to2,
-    Ratio6to5,
-}
-
-impl From<BlackjackPayout> for rules::BlackjackPayout {
-    fn from(value: BlackjackPayout) -> Self {
-        match value {
-            BlackjackPayout::Ratio3to2 => Self::Ratio3to2,
-            BlackjackPayout::Ratio6to5 => Self::Ratio6to5,
-        }
-    }
-}
-
-#[derive(Tsify, Serialize, Deserialize)]
-pub enum SurrenderType {
-    None,
-    Early,
-    Late,
-}
-
-impl From<SurrenderType> for rules::SurrenderType {
-    fn from(value: SurrenderType) -> Self {
-        match value {
-            SurrenderType::None => Self::None,
-            SurrenderType::Early => Self::Early,
-            SurrenderType::Late => Self::Late,
-        }
-    }
-}
-
-#[derive(Tsify, Serialize, Deserialize)]
-pub enum Soft17Rule {
-    Hit,
-    Stand,
-}
-
-impl From<Soft17Rule> for rules::Soft17Rule {
-    fn from(value: Soft17Rule) -> Self {
-        match value {
-            Soft17Rule::Hit => Self::Hit,
-            Soft17Rule::Stand => Self::Stand,
+            strategy::DecisionTable::Hard => Self::Hard,
+            strategy::DecisionTable::Soft => Self::Soft,
+            strategy::DecisionTable::Pair => Self::Pair,
         }
     }
 }
 
 #[derive(Tsify, Serialize, Deserialize)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
-pub struct Rules {
-    #[serde(rename = "blackjackPayout")]
-    pub blackjack_payout: BlackjackPayout,
-    #[serde(rename = "numDecks")]
-    pub num_decks: u8,
-    #[serde(rename = "dealerSoft17")]
-    pub dealer_soft_17: Soft17Rule,
-    #[serde(rename = "doubleAfterSplitAllowed")]
-    pub double_after_split_allowed: bool,
-    #[serde(rename = "surrender")]
-    pub surrender: SurrenderType,
-    #[serde(rename = "maxSplits")]
-    pub max_splits: u8,
+pub struct TableCell {
+    pub table: DecisionTable,
+    pub player: u8,
+    pub dealer: u8,
+    pub value: StrategyValue,
 }
 
-impl From<Rules> for rules::Rules {
-    fn from(value: Rules) -> Self {
+impl From<strategy::TableCell> for TableCell {
+    fn from(value: strategy::TableCell) -> Self {
         Self {
-            blackjack_payout: value.blackjack_payout.into(),
-            num_decks: value.num_decks,
-            dealer_soft_17: value.dealer_soft_17.into(),
-            double_after_split_allowed: value.double_after_split_allowed,
-            surrender: value.surrender.into(),
-            max_splits: value.max_splits,
+            table: value.table.into(),
+            player: value.player_value,
+            dealer: value.dealer_value,
+            value: value.value,
         }
     }
 }
 
+// Values of a row are for the dealer values from `dealer` to 11, `dealer` is only above 2 when
+// cells of the row were already taken with `nextCell`
 #[derive(Tsify, Serialize, Deserialize)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
-pub struct RoundEvs {
-    pub hit: f64,
-    pub stand: f64,
-    pub double: f64,
-    pub split: Option<f64>,
-    pub surrender: Option<f64>,
-}
-
-impl From<strategy::RoundEvs> for RoundEvs {
-    fn from(value: strategy::RoundEvs) -> Self {
-        Self {
-            hit: value.hit,
-            stand: value.stand,
-            double: value.double,
-            split: value.split,
-            surrender: value.surrender,
-        }
-    }
-}
-
-#[derive(Tsify, Serialize, Deserialize)]
-#[tsify(into_wasm_abi, from_wasm_abi)]
-pub struct StrategyValue {
-    pub ev: f64,
-    pub evs: RoundEvs,
-    pub action: PlayerAction,
-}
-
-impl From<strategy::StrategyValue> for StrategyValue {
-    fn from(value: strategy::StrategyValue) -> Self {
-        Self {
-            ev: value.ev,
-            evs: value.evs.into(),
-            action: value.action.into(),
-        }
-    }
-}
-
-#[derive(Tsify, Serialize, Deserialize)]
-#[tsify(into_wasm_abi, from_wasm_abi)]
-pub struct StrategyTable {
+pub struct TableRow {
+    pub table: DecisionTable,
+    pub player: u8,
+    pub dealer: u8,
     pub values: Vec<StrategyValue>,
-    pub from: u8,
-    pub to: u8,
 }
 
-impl From<strategy::StrategyTable> for StrategyTable {
-    fn from(value: strategy::StrategyTable) -> Self {
+impl From<Vec<strategy::TableCell>> for TableRow {
+    fn from(value: Vec<strategy::TableCell>) -> Self {
         Self {
-            values: value.values.iter().map(|v| (*v).into()).collect(),
-            from: value.from,
-            to: value.to,
+            table: value[0].table.into(),
+            player: value[0].player_value,
+            dealer: value[0].dealer_value,
+            values: value.into_iter().map(|cell| cell.value).collect(),
         }
     }
 }
@@ -206,20 +117,84 @@ pub struct StrategyTables {
     pub hard: StrategyTable,
     pub soft: StrategyTable,
     pub pair: StrategyTable,
+    pub options: GeneratorOptions,
 }
 
 #[wasm_bindgen]
 pub struct StrategyGenerator {
     inner: strategy::StrategyGenerator<InfiniteShoe>,
+    stream: Option<strategy::CellStream>,
+}
+
+impl StrategyGenerator {
+    fn precomputed_tables(&self) -> Option<StrategyTables> {
+        let precomputed = tables::precomputed_tables();
+        let rules = self.inner.rules();
+        let options = self.inner.options();
+        let table = |table| precomputed.get(rules, options, table).cloned();
+
+        Some(StrategyTables {
+            hard: table(strategy::DecisionTable::Hard)?,
+            soft: table(strategy::DecisionTable::Soft)?,
+            pair: table(strategy::DecisionTable::Pair)?,
+            options: *options,
+        })
+    }
 }
 
 #[wasm_bindgen]
 impl StrategyGenerator {
     #[wasm_bindgen(constructor)]
-    pub fn new(rules: Rules) -> Self {
+    pub fn new(rules: rules::Rules) -> Result<Self, JsError> {
         let shoe = InfiniteShoe::new();
-        let strategy = strategy::StrategyGenerator::new(rules.into(), shoe);
-        Self { inner: strategy }
+        let strategy = strategy::StrategyGenerator::new(rules, shoe)?;
+        Ok(Self {
+            inner: strategy,
+            stream: None,
+        })
+    }
+
+    #[wasm_bindgen(js_name = withOptions)]
+    pub fn with_options(
+        rules: rules::Rules,
+        options: GeneratorOptions,
+    ) -> Result<StrategyGenerator, JsError> {
+        let shoe = InfiniteShoe::new();
+        let strategy = strategy::StrategyGenerator::with_options(rules, shoe, options)?;
+        Ok(Self {
+            inner: strategy,
+            stream: None,
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn options(&self) -> GeneratorOptions {
+        *self.inner.options()
+    }
+
+    #[wasm_bindgen(js_name = setCacheLimit)]
+    pub fn set_cache_limit(&mut self, bytes: Option<usize>) {
+        self.inner.set_cache_limit(bytes);
+    }
+
+    #[wasm_bindgen(js_name = cacheStats)]
+    pub fn cache_stats(&self) -> CacheStats {
+        self.inner.cache_stats()
+    }
+
+    // Saved caches are only loaded back by generators with the same rules and options
+    #[wasm_bindgen(js_name = saveCaches)]
+    pub fn save_caches(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.inner
+            .save_caches(&mut bytes)
+            .expect("writing to memory cannot fail");
+        bytes
+    }
+
+    #[wasm_bindgen(js_name = loadCaches)]
+    pub fn load_caches(&mut self, bytes: &[u8]) -> Result<bool, JsError> {
+        Ok(self.inner.load_caches(&mut &bytes[..])?)
     }
 
     #[wasm_bindgen]
@@ -231,19 +206,156 @@ impl StrategyGenerator {
         let dealer_upcard = card::Card::from_rank(dealer.into());
         let evs = self.inner.eval_round(hand, dealer_upcard, false);
 
-        evs.best().0.into()
+        evs.best().0
     }
 
     #[wasm_bindgen]
     pub fn tables(&mut self) -> StrategyTables {
+        if let Some(tables) = self.precomputed_tables() {
+            return tables;
+        }
         let hard = self.inner.hard_table();
         let soft = self.inner.soft_table();
         let pair = self.inner.pair_table();
 
         StrategyTables {
-            hard: hard.into(),
-            soft: soft.into(),
-            pair: pair.into(),
+            hard,
+            soft,
+            pair,
+            options: *self.inner.options(),
         }
     }
+
+    // `callback` receives the `Progress` after every solved cell, returning false cancels the
+    // generation. Solving blocks the thread, so a page that wants to cancel runs the generator
+    // in a worker and has the callback check a flag shared with it. An exception thrown by the
+    // callback also stops the generation and is rethrown
+    #[wasm_bindgen(js_name = tablesWithProgress)]
+    pub fn tables_with_progress(
+        &mut self,
+        callback: &js_sys::Function,
+    ) -> Result<StrategyTables, JsValue> {
+        if let Some(tables) = self.precomputed_tables() {
+            return Ok(tables);
+        }
+        let mut thrown = None;
+        let result = self
+            .inner
+            .tables_with_progress(&strategy::DecisionTable::ALL, |progress| {
+                let progress =
+                    serde_wasm_bindgen::to_value(&progress).expect("progress is plain numbers");
+                match callback.call1(&JsValue::NULL, &progress) {
+                    Ok(value) if value.as_bool() == Some(false) => ControlFlow::Break(()),
+                    Ok(_) => ControlFlow::Continue(()),
+                    Err(error) => {
+                        thrown = Some(error);
+                        ControlFlow::Break(())
+                    }
+                }
+            });
+        if let Some(error) = thrown {
+            return Err(error);
+        }
+
+        let [hard, soft, pair]: [StrategyTable; 3] = result
+            .map_err(JsError::from)?
+            .try_into()
+            .expect("every table was requested");
+        Ok(StrategyTables {
+            hard,
+            soft,
+            pair,
+            options: *self.inner.options(),
+        })
+    }
+
+    // Starts solving the hard, soft and pair tables a cell or a row at a time with `nextCell`
+    // and `nextRow`, so the page can render and stay responsive in between. Cells solved by an
+    // earlier stream are cached and come back immediately
+    #[wasm_bindgen(js_name = startTables)]
+    pub fn start_tables(&mut self) {
+        self.stream = Some(strategy::CellStream::new(&strategy::DecisionTable::ALL));
+    }
+
+    // Next cell of the started tables, undefined once they are all solved
+    #[wasm_bindgen(js_name = nextCell)]
+    pub fn next_cell(&mut self) -> Option<TableCell> {
+        let cell = self.stream.as_mut()?.next_cell(&mut self.inner);
+        cell.map(TableCell::from)
+    }
+
+    #[wasm_bindgen(js_name = nextRow)]
+    pub fn next_row(&mut self) -> Option<TableRow> {
+        let row = self.stream.as_mut()?.next_row(&mut self.inner);
+        row.map(TableRow::from)
+    }
+
+    #[wasm_bindgen(js_name = streamProgress)]
+    pub fn stream_progress(&self) -> Option<Progress> {
+        self.stream.as_ref().map(strategy::CellStream::progress)
+    }
+
+    #[wasm_bindgen]
+    pub fn dealer(&mut self) -> DealerTable {
+        self.inner.dealer_table()
+    }
+}
+
+// Strategy for a shoe followed card by card, every dealt card is reported with `removeCard`
+// and the cards of an advised hand once its round is over
+#[wasm_bindgen]
+pub struct LiveAdvisor {
+    inner: strategy::StrategyGenerator<CountShoe>,
+}
+
+#[wasm_bindgen]
+impl LiveAdvisor {
+    #[wasm_bindgen(constructor)]
+    pub fn new(rules: rules::Rules) -> Result<Self, JsError> {
+        Self::with_options(rules, GeneratorOptions::default())
+    }
+
+    #[wasm_bindgen(js_name = withOptions)]
+    pub fn with_options(
+        rules: rules::Rules,
+        options: GeneratorOptions,
+    ) -> Result<LiveAdvisor, JsError> {
+        let shoe = CountShoe::new(rules.num_decks)?;
+        let strategy = strategy::StrategyGenerator::with_options(rules, shoe, options)?;
+        Ok(Self { inner: strategy })
+    }
+
+    #[wasm_bindgen(js_name = removeCard)]
+    pub fn remove_card(&mut self, rank: Rank) -> Result<(), JsError> {
+        self.inner
+            .remove_card(&card::Card::from_rank(rank.into()))?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = cardsLeft)]
+    pub fn cards_left(&self) -> usize {
+        self.inner.shoe().total()
+    }
+
+    #[wasm_bindgen]
+    pub fn evs(&mut self, player: Vec<Rank>, dealer: Rank) -> RoundEvs {
+        let mut hand = Hand::new();
+        for value in player {
+            hand.add_card(&card::Card::from_rank(value.into()));
+        }
+        let dealer_upcard = card::Card::from_rank(dealer.into());
+        self.inner.eval_round(hand, dealer_upcard, hand.is_pair())
+    }
+
+    #[wasm_bindgen]
+    pub fn action(&mut self, player: Vec<Rank>, dealer: Rank) -> PlayerAction {
+        let mut hand = Hand::new();
+        for value in player {
+            hand.add_card(&card::Card::from_rank(value.into()));
+        }
+        let dealer_upcard = card::Card::from_rank(dealer.into());
+        let evs = self.inner.eval_round(hand, dealer_upcard, hand.is_pair());
+
+        evs.best().0
+    }
 }